@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::ConfigInitialized;
+use crate::Config;
+use crate::CONFIG_PREFIX;
+use crate::CONFIG_SIZE;
+use solana_program::{pubkey, pubkey::Pubkey};
+
+/// Deployer key allowed to create the global config once. Afterwards the admin
+/// lives in `Config` and is changed with `update_config_admin`.
+const ADMIN: Pubkey = pubkey!("4bRYs66kGxujekaRGHJjvjP4g7SCou28FZJ8LPDsyDnR");
+#[derive(Accounts)]
+pub struct InitConfigCtx<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = CONFIG_SIZE,
+        seeds = [CONFIG_PREFIX.as_bytes()],
+        bump
+    )]
+    config: Account<'info, Config>,
+    #[account(mut, constraint = payer.key() == ADMIN @ErrorCode::InvalidAdmin)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn init_config_handler(ctx: Context<InitConfigCtx>, admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.bump = ctx.bumps.config;
+    config.admin = admin;
+
+    emit!(ConfigInitialized {
+        admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
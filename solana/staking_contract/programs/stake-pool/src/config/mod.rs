@@ -0,0 +1,8 @@
+mod state;
+pub use state::*;
+
+pub mod init_config;
+pub use init_config::*;
+
+pub mod update_config;
+pub use update_config::*;
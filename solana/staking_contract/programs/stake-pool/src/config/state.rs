@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+pub const CONFIG_SIZE: usize = 8 + 1 + 32;
+pub const CONFIG_PREFIX: &str = "config";
+#[account]
+pub struct Config {
+    pub bump: u8,
+    pub admin: Pubkey,
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::ConfigAdminUpdated;
+use crate::Config;
+use crate::CONFIG_PREFIX;
+
+#[derive(Accounts)]
+pub struct UpdateConfigCtx<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_PREFIX.as_bytes()],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ErrorCode::InvalidAdmin,
+    )]
+    config: Account<'info, Config>,
+    admin: Signer<'info>,
+}
+
+pub fn admin_handler(ctx: Context<UpdateConfigCtx>, new_admin: Pubkey) -> Result<()> {
+    require!(new_admin != Pubkey::default(), ErrorCode::InvalidInput);
+    ctx.accounts.config.admin = new_admin;
+
+    emit!(ConfigAdminUpdated {
+        admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
@@ -23,4 +23,76 @@ pub enum ErrorCode {
     ArithmeticOverflow,
     #[msg("Invalid Input")]
     InvalidInput,
+    #[msg("No Rewards To Claim")]
+    NoRewardsToClaim,
+    #[msg("Insufficient Reward Reserve")]
+    InsufficientRewardReserve,
+    #[msg("Invalid Lock Tier")]
+    InvalidLockTier,
+    #[msg("Lock Tier Does Not Match Staked Position")]
+    LockTierMismatch,
+    #[msg("Invalid reward mint")]
+    InvalidRewardMint,
+    #[msg("Invalid user reward mint token account")]
+    InvalidUserRewardMintTokenAccount,
+    #[msg("Early Unstake Disabled")]
+    EarlyUnstakeDisabled,
+    #[msg("Stake Is Not Locked")]
+    StakeNotLocked,
+    #[msg("Invalid Penalty Treasury")]
+    InvalidPenaltyTreasury,
+    #[msg("Invalid Pending Authority")]
+    InvalidPendingAuthority,
+    #[msg("Pool Is Paused")]
+    PoolPaused,
+    #[msg("Pool Is Not Paused")]
+    PoolNotPaused,
+    #[msg("Stake Entry Is Not Empty")]
+    StakeEntryNotEmpty,
+    #[msg("Invalid Reward Campaign")]
+    InvalidRewardCampaign,
+    #[msg("Nothing To Withdraw")]
+    NothingToWithdraw,
+    #[msg("Unbonding Period Not Over")]
+    UnbondingNotOver,
+    #[msg("Compounding Disabled")]
+    CompoundingDisabled,
+    #[msg("Invalid receipt mint")]
+    InvalidReceiptMint,
+    #[msg("Invalid user receipt token account")]
+    InvalidUserReceiptTokenAccount,
+    #[msg("Pool Has Stakers")]
+    PoolHasStakers,
+    #[msg("Stake Amount Below Minimum")]
+    StakeBelowMinimum,
+    #[msg("Pool Staking Cap Exceeded")]
+    PoolCapExceeded,
+    #[msg("Wallet Staking Cap Exceeded")]
+    WalletCapExceeded,
+    #[msg("Unsupported Account Version")]
+    UnsupportedAccountVersion,
+    #[msg("Invalid Position Accounts")]
+    InvalidPositionAccounts,
+    #[msg("Invalid Voting Power Entries")]
+    InvalidVotingEntries,
+    #[msg("Invalid Snapshot Time")]
+    InvalidSnapshotTime,
+    #[msg("Stake Is Still Vesting")]
+    VestingLocked,
+    #[msg("Invalid Vesting Schedule")]
+    InvalidVestingSchedule,
+    #[msg("Invalid Stake Reward")]
+    InvalidStakeReward,
+    #[msg("Parameter Changes Are Timelocked")]
+    ParamsTimelocked,
+    #[msg("No Pending Parameter Change")]
+    NoPendingParams,
+    #[msg("Parameter Change Not Yet Effective")]
+    ParamsNotEffective,
+    #[msg("Invalid Reward Curve")]
+    InvalidRewardCurve,
+    #[msg("Stake Entry Is Unbonding")]
+    EntryUnbonding,
+    #[msg("Receipts For The Stake Are Not Held")]
+    ReceiptsNotHeld,
 }   
\ No newline at end of file
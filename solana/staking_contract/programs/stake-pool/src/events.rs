@@ -0,0 +1,338 @@
+use anchor_lang::prelude::*;
+use crate::{LockTier, RewardCurve};
+
+#[event]
+pub struct ConfigInitialized {
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigAdminUpdated {
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCreated {
+    pub stake_pool: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub stake_reward: u64,
+    pub min_stake_seconds: u32,
+    pub deposite_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardRateChanged {
+    pub stake_pool: Pubkey,
+    pub old_stake_reward: u64,
+    pub new_stake_reward: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinStakeSecondsChanged {
+    pub stake_pool: Pubkey,
+    pub min_stake_seconds: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignUpdated {
+    pub stake_pool: Pubkey,
+    pub rewards_start: i64,
+    pub rewards_end: i64,
+    pub total_reward_budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockTiersUpdated {
+    pub stake_pool: Pubkey,
+    pub lock_tiers: Vec<LockTier>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyUnstakeConfigUpdated {
+    pub stake_pool: Pubkey,
+    pub penalty_bp: Option<u16>,
+    pub penalty_treasury: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnbondingPeriodChanged {
+    pub stake_pool: Pubkey,
+    pub unbonding_seconds: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompoundingUpdated {
+    pub stake_pool: Pubkey,
+    pub compound_enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeLimitsUpdated {
+    pub stake_pool: Pubkey,
+    pub max_total_staked: u64,
+    pub min_stake_amount: u64,
+    pub max_stake_per_wallet: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolPausedUpdated {
+    pub stake_pool: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub stake_pool: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub stake_pool: Pubkey,
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolParamsProposed {
+    pub stake_pool: Pubkey,
+    pub stake_reward: Option<u64>,
+    pub min_stake_seconds: Option<u32>,
+    pub param_change_delay: Option<u32>,
+    pub reward_curve: Option<Option<RewardCurve>>,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolParamsApplied {
+    pub stake_pool: Pubkey,
+    pub stake_reward: u64,
+    pub min_stake_seconds: Option<u32>,
+    pub param_change_delay: u32,
+    pub reward_curve: Option<RewardCurve>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardCurveUpdated {
+    pub stake_pool: Pubkey,
+    pub reward_curve: Option<RewardCurve>,
+    /// Rate the pool pays from now on, in basis points.
+    pub stake_reward: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolParamsCancelled {
+    pub stake_pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OperatorUpdated {
+    pub stake_pool: Pubkey,
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsDeposited {
+    pub stake_pool: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub reward_reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsWithdrawn {
+    pub stake_pool: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub reward_reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptMintCreated {
+    pub stake_pool: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolMigrated {
+    pub stake_pool: Pubkey,
+    pub version: u8,
+    pub reward_reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EntryCreated {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EntryClosed {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionOpened {
+    pub stake_pool: Pubkey,
+    pub staker_registry: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub position: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EntryMigrated {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub version: u8,
+    pub pending_rewards: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Staked {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub entry_amount: u64,
+    pub lock_seconds: u32,
+    pub multiplier_bp: u16,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakedFor {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub funder: Pubkey,
+    pub beneficiary: Pubkey,
+    pub grant_id: u64,
+    pub amount: u64,
+    pub lock_seconds: u32,
+    pub multiplier_bp: u16,
+    pub vesting_cliff: i64,
+    pub vesting_end: i64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub entry_amount: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnbondingStarted {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub unbonding_amount: u64,
+    pub withdrawable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalCompleted {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyUnstaked {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub forfeited_rewards: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyUnstaked {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Compounded {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub entry_amount: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsPaid {
+    pub stake_pool: Pubkey,
+    pub stake_entry: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub pending_rewards: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VotingPowerSnapshot {
+    pub stake_pool: Pubkey,
+    pub voting_checkpoint: Pubkey,
+    pub staker: Pubkey,
+    pub slot: u64,
+    pub snapshot_at: i64,
+    pub voting_power: u64,
+    pub total_voting_power: u64,
+    pub timestamp: i64,
+}
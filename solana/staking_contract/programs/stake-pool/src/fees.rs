@@ -0,0 +1,110 @@
+//! Transfer-fee policy for Token-2022 mints with a `TransferFeeConfig`.
+//!
+//! The pool books exactly the amount that moves through its vaults:
+//! - inbound transfers (stake, reward deposits) are grossed up so the vault receives
+//!   the full `amount`, the sender pays the fee on top;
+//! - outbound transfers (unstake, rewards, withdrawals, penalties) debit the vault by
+//!   exactly `amount`, the fee is withheld from what the recipient receives.
+//!
+//! Legacy SPL Token mints and Token-2022 mints without the extension pay no fee.
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+    },
+    token_interface::{self, Mint, TokenAccount, TokenInterface},
+};
+use crate::errors::ErrorCode;
+use crate::StakePool;
+use crate::STAKE_POOL_PREFIX;
+
+/// Moves `amount` into a pool vault, charging the transfer fee to `authority`.
+pub fn transfer_in<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    amount: u64,
+) -> Result<()> {
+    let transfer_fee = get_transfer_inverse_fee(&mint.to_account_info(), amount)?;
+    let transfer_amount = amount
+        .checked_add(transfer_fee)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.to_account_info(),
+            },
+        ),
+        transfer_amount,
+        mint.decimals,
+    )
+}
+
+/// Moves `amount` out of a pool vault signed by the pool PDA. The recipient bears the fee.
+pub fn transfer_out<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    stake_pool: &Account<'info, StakePool>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[
+        STAKE_POOL_PREFIX.as_bytes(),
+        stake_pool.identifier.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer = [&seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: stake_pool.to_account_info(),
+            },
+            &signer,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Fee to add on top of `post_fee_amount` so the recipient receives exactly `post_fee_amount`.
+pub fn get_transfer_inverse_fee(mint_info: &AccountInfo, post_fee_amount: u64) -> Result<u64> {
+    if *mint_info.owner == Token::id() || post_fee_amount == 0 {
+        return Ok(0);
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    let fee = if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+        let epoch = Clock::get()?.epoch;
+
+        let transfer_fee = transfer_fee_config.get_epoch_fee(epoch);
+        if u16::from(transfer_fee.transfer_fee_basis_points) == MAX_FEE_BASIS_POINTS {
+            u64::from(transfer_fee.maximum_fee)
+        } else {
+            transfer_fee_config
+                .calculate_inverse_epoch_fee(epoch, post_fee_amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?
+        }
+    } else {
+        0
+    };
+    Ok(fee)
+}
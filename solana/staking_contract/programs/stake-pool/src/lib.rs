@@ -8,18 +8,41 @@ pub use stake_entry::*;
 pub mod stake_unstake;
 pub use stake_unstake::*;
 
+pub mod config;
+pub use config::*;
+
+pub mod staker_registry;
+pub use staker_registry::*;
+
+pub mod voting;
+pub use voting::*;
+
 pub mod errors;
 
+pub mod events;
+
+pub mod fees;
+
+pub mod utils;
+
 declare_id!("FbSXzbQNgxERQkYzMsnyg7ckSKCCANHo62k23ULuF39Z");
 
 #[program]
 mod staking_22 {
     use super::*;
+    pub fn init_config(ctx: Context<InitConfigCtx>, admin: Pubkey) -> Result<()> {
+        config::init_config::init_config_handler(ctx, admin)
+    }
+
+    pub fn update_config_admin(ctx: Context<UpdateConfigCtx>, new_admin: Pubkey) -> Result<()> {
+        config::update_config::admin_handler(ctx, new_admin)
+    }
+
     pub fn init_pool(ctx: Context<InitPoolCtx>, ix: InitPoolIx) -> Result<()> {
-        stake_pool::init_pool::handler(ctx, ix)
+        stake_pool::init_pool::init_pool_handler(ctx, ix)
     }
 
-    pub fn update_pool_reward(ctx: Context<UpdatePoolCtx>, stake_reward: u64) -> Result<()> {
+    pub fn update_pool_reward(ctx: Context<UpdatePoolAuthorityCtx>, stake_reward: u64) -> Result<()> {
         stake_pool::update_pool::reward_handler(ctx, stake_reward)
     }
 
@@ -30,23 +53,205 @@ mod staking_22 {
         stake_pool::update_pool::time_handler(ctx, min_stake_seconds)
     }
 
+    pub fn update_pool_lock_tiers(
+        ctx: Context<UpdatePoolCtx>,
+        lock_tiers: Vec<LockTier>,
+    ) -> Result<()> {
+        stake_pool::update_pool::lock_tiers_handler(ctx, lock_tiers)
+    }
+
+    pub fn update_pool_early_unstake(
+        ctx: Context<UpdatePoolAuthorityCtx>,
+        penalty_bp: Option<u16>,
+        penalty_treasury: Option<Pubkey>,
+    ) -> Result<()> {
+        stake_pool::update_pool::penalty_handler(ctx, penalty_bp, penalty_treasury)
+    }
+
+    pub fn update_pool_campaign(
+        ctx: Context<UpdatePoolAuthorityCtx>,
+        rewards_start: i64,
+        rewards_end: i64,
+        total_reward_budget: u64,
+    ) -> Result<()> {
+        stake_pool::update_pool::campaign_handler(
+            ctx,
+            rewards_start,
+            rewards_end,
+            total_reward_budget,
+        )
+    }
+
+    pub fn update_pool_unbonding(ctx: Context<UpdatePoolAuthorityCtx>, unbonding_seconds: u32) -> Result<()> {
+        stake_pool::update_pool::unbonding_handler(ctx, unbonding_seconds)
+    }
+
+    pub fn update_pool_compound(ctx: Context<UpdatePoolCtx>, compound_enabled: bool) -> Result<()> {
+        stake_pool::update_pool::compound_enabled_handler(ctx, compound_enabled)
+    }
+
+    pub fn update_pool_limits(
+        ctx: Context<UpdatePoolAuthorityCtx>,
+        max_total_staked: u64,
+        min_stake_amount: u64,
+        max_stake_per_wallet: u64,
+    ) -> Result<()> {
+        stake_pool::update_pool::limits_handler(
+            ctx,
+            max_total_staked,
+            min_stake_amount,
+            max_stake_per_wallet,
+        )
+    }
+
+    pub fn update_pool_paused(ctx: Context<UpdatePoolCtx>, paused: bool) -> Result<()> {
+        stake_pool::update_pool::paused_handler(ctx, paused)
+    }
+
+    pub fn init_receipt_mint(ctx: Context<InitReceiptMintCtx>) -> Result<()> {
+        stake_pool::receipt::receipt_handler(ctx)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePoolCtx>) -> Result<()> {
+        stake_pool::migrate_pool::migrate_pool_handler(ctx)
+    }
+
+    pub fn propose_pool_authority(
+        ctx: Context<PoolAuthorityCtx>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        stake_pool::authority::propose_handler(ctx, new_authority)
+    }
+
+    pub fn accept_pool_authority(ctx: Context<AcceptPoolAuthorityCtx>) -> Result<()> {
+        stake_pool::authority::accept_handler(ctx)
+    }
+
+    pub fn set_pool_operator(ctx: Context<PoolAuthorityCtx>, operator: Pubkey) -> Result<()> {
+        stake_pool::authority::operator_handler(ctx, operator)
+    }
+
+    pub fn propose_pool_params(
+        ctx: Context<PoolParamsCtx>,
+        stake_reward: Option<u64>,
+        min_stake_seconds: Option<u32>,
+        param_change_delay: Option<u32>,
+        reward_curve: Option<Option<RewardCurve>>,
+    ) -> Result<()> {
+        stake_pool::params::propose_params_handler(
+            ctx,
+            stake_reward,
+            min_stake_seconds,
+            param_change_delay,
+            reward_curve,
+        )
+    }
+
+    pub fn cancel_pool_params(ctx: Context<PoolParamsCtx>) -> Result<()> {
+        stake_pool::params::cancel_handler(ctx)
+    }
+
+    pub fn apply_pool_params(ctx: Context<ApplyPoolParamsCtx>) -> Result<()> {
+        stake_pool::params::apply_handler(ctx)
+    }
+
+    pub fn update_pool_reward_curve(
+        ctx: Context<PoolParamsCtx>,
+        reward_curve: Option<RewardCurve>,
+    ) -> Result<()> {
+        stake_pool::params::curve_handler(ctx, reward_curve)
+    }
+
     pub fn withdraw_tokens(ctx: Context<WithdrawTokensCtx>, amount: u64) -> Result<()> {
-        stake_pool::withdraw::handler(ctx, amount)
+        stake_pool::withdraw::withdraw_handler(ctx, amount)
     }
 
     pub fn deposite_tokens(ctx: Context<DepositeTokensCtx>, amount: u64) -> Result<()> {
-        stake_pool::deposite::handler(ctx, amount)
+        stake_pool::deposite::deposite_handler(ctx, amount)
     }
 
     pub fn init_entry(ctx: Context<InitEntryCtx>, identifier: String) -> Result<()> {
-        stake_entry::init_entry::handler(ctx, identifier)
+        stake_entry::init_entry::init_entry_handler(ctx, identifier)
+    }
+
+    pub fn close_entry(ctx: Context<CloseEntryCtx>) -> Result<()> {
+        stake_entry::close_entry::close_entry_handler(ctx)
+    }
+
+    pub fn open_position(ctx: Context<OpenPositionCtx>) -> Result<()> {
+        staker_registry::open_position::open_position_handler(ctx)
+    }
+
+    pub fn aggregate_positions(ctx: Context<AggregatePositionsCtx>, start: u32, len: u32) -> Result<PositionsSummary> {
+        staker_registry::aggregate_positions::aggregate_positions_handler(ctx, start, len)
+    }
+
+    pub fn get_voting_power(ctx: Context<GetVotingPowerCtx>, staker: Pubkey) -> Result<u64> {
+        voting::get_voting_power::get_voting_power_handler(ctx, staker)
+    }
+
+    pub fn snapshot_voting_power(ctx: Context<SnapshotVotingPowerCtx>, snapshot_at: i64) -> Result<()> {
+        voting::snapshot::snapshot_handler(ctx, snapshot_at)
+    }
+
+    pub fn migrate_entry(ctx: Context<MigrateEntryCtx>) -> Result<()> {
+        stake_entry::migrate_entry::migrate_entry_handler(ctx)
+    }
+
+    pub fn stake_tokens(
+        ctx: Context<StakeTokenCtx>,
+        amount: u64,
+        lock_tier: Option<u8>,
+    ) -> Result<()> {
+        stake_unstake::stake::stake_handler(ctx, amount, lock_tier)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn stake_for(
+        ctx: Context<StakeForCtx>,
+        beneficiary: Pubkey,
+        grant_id: u64,
+        amount: u64,
+        lock_tier: Option<u8>,
+        cliff_seconds: u32,
+        vesting_seconds: u32,
+    ) -> Result<()> {
+        stake_unstake::stake_for::stake_for_handler(
+            ctx,
+            beneficiary,
+            grant_id,
+            amount,
+            lock_tier,
+            cliff_seconds,
+            vesting_seconds,
+        )
+    }
+
+    pub fn unstake_tokens(ctx: Context<UnstakeTokenCtx>, close_entry: bool) -> Result<()> {
+        stake_unstake::unstake::unstake_handler(ctx, close_entry)
+    }
+
+    pub fn unstake_partial(ctx: Context<UnstakeTokenCtx>, amount: u64) -> Result<()> {
+        stake_unstake::unstake::partial_handler(ctx, amount)
+    }
+
+    pub fn early_unstake_tokens(ctx: Context<EarlyUnstakeTokenCtx>) -> Result<()> {
+        stake_unstake::early_unstake::early_unstake_handler(ctx)
+    }
+
+    pub fn emergency_unstake(ctx: Context<EmergencyUnstakeCtx>) -> Result<()> {
+        stake_unstake::emergency_unstake::emergency_unstake_handler(ctx)
+    }
+
+    pub fn complete_withdrawal(ctx: Context<CompleteWithdrawalCtx>) -> Result<()> {
+        stake_unstake::complete_withdrawal::complete_withdrawal_handler(ctx)
     }
 
-    pub fn stake_tokens(ctx: Context<StakeTokenCtx>, amount: u64) -> Result<()> {
-        stake_unstake::stake::handler(ctx, amount)
+    pub fn claim_rewards(ctx: Context<ClaimRewardsCtx>) -> Result<()> {
+        stake_unstake::claim::claim_handler(ctx)
     }
 
-    pub fn unstake_tokens(ctx: Context<UnstakeTokenCtx>) -> Result<()> {
-        stake_unstake::unstake::handler(ctx)
+    pub fn compound(ctx: Context<CompoundCtx>) -> Result<()> {
+        stake_unstake::compound::compound_handler(ctx)
     }
 }
\ No newline at end of file
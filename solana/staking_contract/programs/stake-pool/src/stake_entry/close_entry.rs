@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::EntryClosed;
+use crate::StakeEntry;
+use crate::STAKE_ENTRY_VERSION;
+
+#[derive(Accounts)]
+pub struct CloseEntryCtx<'info> {
+    #[account(
+        mut,
+        close = user,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.is_empty() @ErrorCode::StakeEntryNotEmpty,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    #[account(mut)]
+    user: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn close_entry_handler(ctx: Context<CloseEntryCtx>) -> Result<()> {
+    let stake_entry = &ctx.accounts.stake_entry;
+
+    emit!(EntryClosed {
+        stake_pool: stake_entry.stake_pool,
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
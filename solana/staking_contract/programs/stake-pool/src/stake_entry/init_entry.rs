@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::EntryCreated;
 use crate::StakeEntry;
 use crate::StakePool;
 use crate::STAKE_ENTRY_PREFIX;
 use crate::STAKE_ENTRY_SIZE;
+use crate::STAKE_ENTRY_VERSION;
+use crate::BASE_MULTIPLIER_BP;
+use crate::STAKE_POOL_VERSION;
 
 #[derive(Accounts)]
 #[instruction(identifier: String)]
@@ -15,20 +20,32 @@ pub struct InitEntryCtx<'info> {
         bump,
     )]
     stake_entry: Box<Account<'info, StakeEntry>>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
     stake_pool: Box<Account<'info, StakePool>>,
     #[account(mut)]
     payer: Signer<'info>,
     system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<InitEntryCtx>, identifier: String) -> Result<()> {
+pub fn init_entry_handler(ctx: Context<InitEntryCtx>, identifier: String) -> Result<()> {
     let stake_entry = &mut ctx.accounts.stake_entry;
+    stake_entry.version = STAKE_ENTRY_VERSION;
     stake_entry.bump = ctx.bumps.stake_entry;
     stake_entry.stake_pool = ctx.accounts.stake_pool.key();
     stake_entry.staker = ctx.accounts.payer.key();
     stake_entry.amount = 0;
+    stake_entry.multiplier_bp = BASE_MULTIPLIER_BP;
     stake_entry.identifier = identifier;
 
+    emit!(EntryCreated {
+        stake_pool: stake_entry.stake_pool,
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     Ok(())
 }
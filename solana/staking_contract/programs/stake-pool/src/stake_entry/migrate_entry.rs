@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::EntryMigrated;
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct MigrateEntryCtx<'info> {
+    /// CHECK: Stake entry account in any supported layout, see `StakeEntry::migrate_to_latest`
+    #[account(mut, owner = crate::ID)]
+    stake_entry: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn migrate_entry_handler(ctx: Context<MigrateEntryCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.update_rewards(Clock::get()?.unix_timestamp)?;
+
+    let stake_entry = StakeEntry::migrate_to_latest(
+        &ctx.accounts.stake_entry,
+        stake_pool,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(EntryMigrated {
+        stake_pool: stake_pool.key(),
+        stake_entry: ctx.accounts.stake_entry.key(),
+        staker: stake_entry.staker,
+        version: stake_entry.version,
+        pending_rewards: stake_entry.pending_rewards,
+        timestamp: stake_pool.last_update_time,
+    });
+
+    Ok(())
+}
@@ -1,5 +1,11 @@
-pub mod state;
+mod state;
 pub use state::*;
 
 pub mod init_entry;
-pub use init_entry::*;
\ No newline at end of file
+pub use init_entry::*;
+
+pub mod close_entry;
+pub use close_entry::*;
+
+pub mod migrate_entry;
+pub use migrate_entry::*;
@@ -1,13 +1,394 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::errors::ErrorCode;
+use crate::get_rewards;
+use crate::utils::transfer_sols;
+use crate::{StakePool, BASE_MULTIPLIER_BP, MAX_IDENTIFIER_LEN, SECONDS_IN_YEAR};
 
 pub const STAKE_ENTRY_PREFIX: &str = "stake-entry";
-pub const STAKE_ENTRY_SIZE: usize = 8 + std::mem::size_of::<StakeEntry>() + 8;
+/// Entries opened by `stake_for` live at `[GRANT_PREFIX, stake_pool, beneficiary, funder, grant_id]`.
+pub const GRANT_PREFIX: &str = "grant";
+pub const STAKE_ENTRY_SIZE: usize = 8 + StakeEntry::INIT_SPACE;
+pub const STAKE_ENTRY_VERSION: u8 = 3;
+
+/// Latest entry version: [`STAKE_ENTRY_VERSION`]
+/// Like pools, entries are migrated explicitly: after every layout bump each live
+/// entry has to go through `migrate_entry` (see `StakeEntry::migrate_to_latest`)
+/// once its pool has been migrated.
 #[account]
+#[derive(InitSpace)]
 pub struct StakeEntry {
+    pub version: u8,
+    pub bump: u8,
+    pub staker: Pubkey,
+    pub stake_pool: Pubkey,
+    pub amount: u64,
+    pub last_staked_at: i64,
+    #[max_len(MAX_IDENTIFIER_LEN)]
+    pub identifier: String,
+    pub reward_per_token_paid: u128,
+    pub pending_rewards: u64,
+    pub lock_seconds: u32,
+    pub multiplier_bp: u16,
+    pub unbonding_amount: u64,
+    /// When `unbonding_amount` can be withdrawn, pushed back by every unstake.
+    pub withdrawable_at: i64,
+    /// Stake granted through `stake_for`, vesting to the staker.
+    pub grant: Option<Grant>,
+    /// History of `amount` for `voting_power`: when it last grew (stake, grant or
+    /// compound), the amount held before that and since when it was held.
+    /// Withdrawals lower `prior_amount` too, it never exceeds `amount`.
+    pub amount_increased_at: i64,
+    pub prior_amount: u64,
+    pub prior_amount_since: i64,
+}
+
+/// Tokens a funder staked on behalf of `StakeEntry::staker`. Nothing vests before
+/// `cliff`, then `amount` vests linearly from `start` to `end`. Unvested tokens
+/// cannot leave the pool.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub struct Grant {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakeEntryV2 {
+    pub version: u8,
     pub bump: u8,
     pub staker: Pubkey,
     pub stake_pool: Pubkey,
     pub amount: u64,
     pub last_staked_at: i64,
+    #[max_len(MAX_IDENTIFIER_LEN)]
     pub identifier: String,
-}
\ No newline at end of file
+    pub reward_per_token_paid: u128,
+    pub pending_rewards: u64,
+    pub lock_seconds: u32,
+    pub multiplier_bp: u16,
+    pub unbonding_amount: u64,
+    pub withdrawable_at: i64,
+    pub grant: Option<Grant>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakeEntryV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub staker: Pubkey,
+    pub stake_pool: Pubkey,
+    pub amount: u64,
+    pub last_staked_at: i64,
+    #[max_len(MAX_IDENTIFIER_LEN)]
+    pub identifier: String,
+    pub reward_per_token_paid: u128,
+    pub pending_rewards: u64,
+    pub lock_seconds: u32,
+    pub multiplier_bp: u16,
+    pub unbonding_amount: u64,
+    pub withdrawable_at: i64,
+}
+
+#[account]
+pub struct StakeEntryV0 {
+    pub bump: u8,
+    pub staker: Pubkey,
+    pub stake_pool: Pubkey,
+    pub amount: u64,
+    pub last_staked_at: i64,
+    pub identifier: String,
+}
+
+impl StakeEntry {
+    /// Moves rewards earned since the last checkpoint into `pending_rewards`.
+    /// `reward_per_token` must come from an up to date `StakePool`.
+    pub fn settle_rewards(&mut self, reward_per_token: u128) -> Result<()> {
+        let earned = get_rewards(
+            u128::from(self.amount),
+            reward_per_token,
+            self.reward_per_token_paid,
+            u128::from(self.multiplier_bp),
+        )?;
+        self.pending_rewards = self
+            .pending_rewards
+            .checked_add(earned)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.reward_per_token_paid = reward_per_token;
+        Ok(())
+    }
+
+    /// An entry can be closed once it holds no principal, nothing unbonding and no unpaid rewards.
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0 && self.unbonding_amount == 0 && self.pending_rewards == 0
+    }
+
+    /// Seconds the position has to stay staked: the pool minimum or the
+    /// entry's lock tier, whichever is longer.
+    pub fn required_stake_seconds(&self, min_stake_seconds: Option<u32>) -> u32 {
+        min_stake_seconds.unwrap_or(0).max(self.lock_seconds)
+    }
+
+    /// Principal that can leave the pool at `now`, everything but the unvested grant.
+    pub fn withdrawable_amount(&self, now: i64) -> Result<u64> {
+        Ok(self.amount.saturating_sub(self.unvested_amount(now)?))
+    }
+
+    pub fn grant_address(stake_pool: &Pubkey, beneficiary: &Pubkey, funder: &Pubkey, grant_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                GRANT_PREFIX.as_bytes(),
+                stake_pool.as_ref(),
+                beneficiary.as_ref(),
+                funder.as_ref(),
+                &grant_id.to_le_bytes(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    /// Granted tokens that have not vested yet at `now`.
+    pub fn unvested_amount(&self, now: i64) -> Result<u64> {
+        let grant = match self.grant {
+            Some(grant) => grant,
+            None => return Ok(0),
+        };
+        if now < grant.cliff {
+            return Ok(grant.amount);
+        }
+        if now >= grant.end {
+            return Ok(0);
+        }
+        let vested = u128::from(grant.amount)
+            .checked_mul(u128::from((now - grant.start) as u64))
+            .and_then(|f| f.checked_div(u128::from((grant.end - grant.start) as u64)))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(grant.amount - vested as u64)
+    }
+
+    /// Whether the position is still inside its required stake period at `now`.
+    pub fn is_locked(&self, min_stake_seconds: Option<u32>, now: i64) -> bool {
+        let required_stake_seconds = self.required_stake_seconds(min_stake_seconds);
+        required_stake_seconds > 0
+            && now.saturating_sub(self.last_staked_at) < i64::from(required_stake_seconds)
+    }
+}
+
+impl StakeEntry {
+    /// Migrates the entry in the provided account to the latest version, one layout
+    /// at a time, and returns it. Only called by `migrate_entry`. `stake_pool` must
+    /// already be migrated and up to date.
+    pub fn migrate_to_latest<'info>(
+        stake_entry_info: &AccountInfo<'info>,
+        stake_pool: &Account<'info, StakePool>,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<Box<Self>> {
+        let data = stake_entry_info.try_borrow_data()?;
+        let expected_v0_size = 8 + StakeEntryV0::INIT_SPACE;
+        let data_size = data.len();
+        drop(data);
+
+        // Migrate to V1 if needed
+        if data_size == expected_v0_size {
+            msg!("Migrating stake entry account to V1");
+            Self::migrate_to_v1(stake_entry_info, stake_pool, payer, system_program)?;
+        }
+
+        // Migrate to V2 if needed
+        let data = stake_entry_info.try_borrow_data()?;
+        let version = data[8];
+        drop(data);
+        if version == 1 {
+            msg!("Migrating stake entry account to V2");
+            Self::migrate_to_v2(stake_entry_info, payer, system_program)?;
+        }
+
+        // Migrate to V3 if needed
+        let data = stake_entry_info.try_borrow_data()?;
+        let version = data[8];
+        drop(data);
+        if version == 2 {
+            msg!("Migrating stake entry account to V3");
+            Self::migrate_to_v3(stake_entry_info, payer, system_program)?;
+        }
+
+        let data = stake_entry_info.try_borrow_data()?;
+        let stake_entry = Box::new(StakeEntry::try_deserialize(&mut &data[..])?);
+        require!(stake_entry.version == STAKE_ENTRY_VERSION, ErrorCode::UnsupportedAccountVersion);
+
+        Ok(stake_entry)
+    }
+
+    fn migrate_to_v1<'info>(
+        stake_entry_info: &AccountInfo<'info>,
+        stake_pool: &Account<'info, StakePool>,
+        payer: &Signer<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let data = stake_entry_info.try_borrow_data()?;
+        require!(data[..8] == StakeEntry::DISCRIMINATOR, ErrorCode::UnsupportedAccountVersion);
+        let old_struct = StakeEntryV0::deserialize(&mut &data[8..])?;
+        drop(data);
+        require!(old_struct.stake_pool == stake_pool.key(), ErrorCode::InvalidStakePool);
+
+        // V0 entries earned `stake_reward` on their amount since `last_staked_at`,
+        // book that as pending and accrue through the pool accumulator from here on
+        let now = Clock::get()?.unix_timestamp;
+        let elapsed = u128::from(now.saturating_sub(old_struct.last_staked_at) as u64);
+        let pending_rewards = u128::from(old_struct.amount)
+            .checked_mul(u128::from(stake_pool.stake_reward))
+            .and_then(|f| f.checked_div(10000))
+            .and_then(|f| f.checked_mul(elapsed))
+            .and_then(|f| f.checked_div(SECONDS_IN_YEAR))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        let new_struct = StakeEntryV1 {
+            version: 1,
+            bump: old_struct.bump,
+            staker: old_struct.staker,
+            stake_pool: old_struct.stake_pool,
+            amount: old_struct.amount,
+            last_staked_at: old_struct.last_staked_at,
+            identifier: old_struct.identifier,
+            reward_per_token_paid: stake_pool.reward_per_token_stored,
+            pending_rewards: u64::try_from(pending_rewards).map_err(|_| ErrorCode::ArithmeticOverflow)?,
+            lock_seconds: 0,
+            multiplier_bp: BASE_MULTIPLIER_BP,
+            unbonding_amount: 0,
+            withdrawable_at: 0,
+        };
+
+        let new_space = 8 + StakeEntryV1::INIT_SPACE;
+        stake_entry_info.realloc(new_space, false)?;
+
+        let old_rent = Rent::get()?.minimum_balance(8 + StakeEntryV0::INIT_SPACE);
+        let new_rent = Rent::get()?.minimum_balance(new_space);
+
+        if new_rent > old_rent {
+            let additional_lamports = new_rent
+                .checked_sub(old_rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            transfer_sols(payer, stake_entry_info, system_program, additional_lamports)?;
+        }
+
+        let mut account_data = stake_entry_info.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut account_data[8..]);
+
+        new_struct.serialize(&mut cursor)?;
+
+        Ok(())
+    }
+
+    fn migrate_to_v2<'info>(
+        stake_entry_info: &AccountInfo<'info>,
+        payer: &Signer<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let data = stake_entry_info.try_borrow_data()?;
+        require!(data[..8] == StakeEntry::DISCRIMINATOR, ErrorCode::UnsupportedAccountVersion);
+        let old_struct = StakeEntryV1::deserialize(&mut &data[8..])?;
+        drop(data);
+
+        let new_struct = StakeEntryV2 {
+            version: 2,
+            bump: old_struct.bump,
+            staker: old_struct.staker,
+            stake_pool: old_struct.stake_pool,
+            amount: old_struct.amount,
+            last_staked_at: old_struct.last_staked_at,
+            identifier: old_struct.identifier,
+            reward_per_token_paid: old_struct.reward_per_token_paid,
+            pending_rewards: old_struct.pending_rewards,
+            lock_seconds: old_struct.lock_seconds,
+            multiplier_bp: old_struct.multiplier_bp,
+            unbonding_amount: old_struct.unbonding_amount,
+            withdrawable_at: old_struct.withdrawable_at,
+            grant: None,
+        };
+
+        let new_space = 8 + StakeEntryV2::INIT_SPACE;
+        stake_entry_info.realloc(new_space, false)?;
+
+        let old_rent = Rent::get()?.minimum_balance(8 + StakeEntryV1::INIT_SPACE);
+        let new_rent = Rent::get()?.minimum_balance(new_space);
+
+        if new_rent > old_rent {
+            let additional_lamports = new_rent
+                .checked_sub(old_rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            transfer_sols(payer, stake_entry_info, system_program, additional_lamports)?;
+        }
+
+        let mut account_data = stake_entry_info.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut account_data[8..]);
+
+        new_struct.serialize(&mut cursor)?;
+
+        Ok(())
+    }
+
+    fn migrate_to_v3<'info>(
+        stake_entry_info: &AccountInfo<'info>,
+        payer: &Signer<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let data = stake_entry_info.try_borrow_data()?;
+        require!(data[..8] == StakeEntry::DISCRIMINATOR, ErrorCode::UnsupportedAccountVersion);
+        let old_struct = StakeEntryV2::deserialize(&mut &data[8..])?;
+        drop(data);
+
+        // without a history the entry keeps voting from its last stake only
+        let new_struct = StakeEntry {
+            version: 3,
+            bump: old_struct.bump,
+            staker: old_struct.staker,
+            stake_pool: old_struct.stake_pool,
+            amount: old_struct.amount,
+            last_staked_at: old_struct.last_staked_at,
+            identifier: old_struct.identifier,
+            reward_per_token_paid: old_struct.reward_per_token_paid,
+            pending_rewards: old_struct.pending_rewards,
+            lock_seconds: old_struct.lock_seconds,
+            multiplier_bp: old_struct.multiplier_bp,
+            unbonding_amount: old_struct.unbonding_amount,
+            withdrawable_at: old_struct.withdrawable_at,
+            grant: old_struct.grant,
+            amount_increased_at: old_struct.last_staked_at,
+            prior_amount: 0,
+            prior_amount_since: old_struct.last_staked_at,
+        };
+
+        let new_space = 8 + StakeEntry::INIT_SPACE;
+        stake_entry_info.realloc(new_space, false)?;
+
+        let old_rent = Rent::get()?.minimum_balance(8 + StakeEntryV2::INIT_SPACE);
+        let new_rent = Rent::get()?.minimum_balance(new_space);
+
+        if new_rent > old_rent {
+            let additional_lamports = new_rent
+                .checked_sub(old_rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            transfer_sols(payer, stake_entry_info, system_program, additional_lamports)?;
+        }
+
+        let mut account_data = stake_entry_info.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut account_data[8..]);
+
+        new_struct.serialize(&mut cursor)?;
+
+        Ok(())
+    }
+}
+
+impl Space for StakeEntryV0 {
+    const INIT_SPACE: usize = std::mem::size_of::<StakeEntryV0>() + 8;
+}
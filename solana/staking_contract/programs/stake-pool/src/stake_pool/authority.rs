@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::{AuthorityAccepted, AuthorityProposed, OperatorUpdated};
+use crate::StakePool;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct PoolAuthorityCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_pool.authority == authority.key() @ErrorCode::InvalidAdmin,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Account<'info, StakePool>,
+    authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptPoolAuthorityCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_pool.pending_authority == Some(new_authority.key()) @ErrorCode::InvalidPendingAuthority,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Account<'info, StakePool>,
+    new_authority: Signer<'info>,
+}
+
+/// First step of an authority transfer, the new authority has to accept it.
+/// Proposing `None` cancels a pending transfer.
+pub fn propose_handler(ctx: Context<PoolAuthorityCtx>, new_authority: Option<Pubkey>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.pending_authority = new_authority;
+
+    emit!(AuthorityProposed {
+        stake_pool: stake_pool.key(),
+        authority: stake_pool.authority,
+        pending_authority: new_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Second step of an authority transfer. The operator is reset to the new authority,
+/// the previous team keeps no role in the pool.
+pub fn accept_handler(ctx: Context<AcceptPoolAuthorityCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let previous_authority = stake_pool.authority;
+    stake_pool.authority = ctx.accounts.new_authority.key();
+    stake_pool.operator = stake_pool.authority;
+    stake_pool.pending_authority = None;
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    emit!(AuthorityAccepted {
+        stake_pool: stake_pool.key(),
+        previous_authority,
+        authority: stake_pool.authority,
+        timestamp,
+    });
+    emit!(OperatorUpdated {
+        stake_pool: stake_pool.key(),
+        operator: stake_pool.operator,
+        timestamp,
+    });
+    Ok(())
+}
+
+pub fn operator_handler(ctx: Context<PoolAuthorityCtx>, operator: Pubkey) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.operator = operator;
+
+    emit!(OperatorUpdated {
+        stake_pool: stake_pool.key(),
+        operator,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
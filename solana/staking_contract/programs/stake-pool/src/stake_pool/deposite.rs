@@ -1,55 +1,71 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token_2022::Token2022;
-use anchor_spl::token_interface::{self, Mint, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use crate::errors::ErrorCode;
+use crate::fees::transfer_in;
+use crate::events::RewardsDeposited;
 use crate::StakePool;
+use crate::STAKE_POOL_VERSION;
 
 #[derive(Accounts)]
 pub struct DepositeTokensCtx<'info> {
-    #[account(mut, constraint = stake_pool.authority == user.key() @ErrorCode::InvalidAdmin,)]
+    #[account(
+        mut,
+        constraint = stake_pool.authority == user.key() @ErrorCode::InvalidAdmin,
+        constraint = !stake_pool.paused @ErrorCode::PoolPaused,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
     stake_pool: Box<Account<'info, StakePool>>,
     #[account(
         mut,
-        associated_token::mint = stake_mint,
+        associated_token::mint = reward_mint,
         associated_token::authority = stake_pool,
         associated_token::token_program = token_program,
     )]
-    pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint)]
-    stake_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(constraint = stake_pool.reward_mint == reward_mint.key() @ ErrorCode::InvalidRewardMint)]
+    reward_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(mut)]
     user: Signer<'info>,
     #[account(mut, constraint =
         user_token_account.amount > 0
-        && user_token_account.mint == stake_mint.key()
+        && user_token_account.mint == reward_mint.key()
         && user_token_account.owner == user.key()
-        @ ErrorCode::InvalidUserStakeMintTokenAccount
+        @ ErrorCode::InvalidUserRewardMintTokenAccount
     )]
     user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    token_program: Program<'info, Token2022>,
+    token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<DepositeTokensCtx>, amount: u64) -> Result<()> {
-
-    token_interface::transfer_checked(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token_interface::TransferChecked {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                mint: ctx.accounts.stake_mint.to_account_info(),
-                to: ctx.accounts.pool_token_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
+pub fn deposite_handler(ctx: Context<DepositeTokensCtx>, amount: u64) -> Result<()> {
+
+    transfer_in(
+        &ctx.accounts.token_program,
+        &ctx.accounts.reward_mint,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.reward_vault,
+        &ctx.accounts.user,
         amount,
-        ctx.accounts.stake_mint.decimals,
     )?;
 
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.reward_reserve = stake_pool
+        .reward_reserve
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(RewardsDeposited {
+        stake_pool: stake_pool.key(),
+        authority: ctx.accounts.user.key(),
+        amount,
+        reward_reserve: stake_pool.reward_reserve,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     Ok(())
 }
\ No newline at end of file
@@ -1,16 +1,18 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_2022::Token2022,
-    token_interface::{self, Mint, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 use crate::errors::ErrorCode;
+use crate::fees::transfer_in;
+use crate::events::PoolCreated;
+use crate::Config;
 use crate::StakePool;
+use crate::CONFIG_PREFIX;
 use crate::STAKE_POOL_DEFAULT_SIZE;
 use crate::STAKE_POOL_PREFIX;
-use solana_program::{pubkey, pubkey::Pubkey};
+use crate::STAKE_POOL_VERSION;
 
-const ADMIN: Pubkey = pubkey!("4bRYs66kGxujekaRGHJjvjP4g7SCou28FZJ8LPDsyDnR");
 #[derive(Accounts)]
 #[instruction(ix: InitPoolIx)]
 pub struct InitPoolCtx<'info> {
@@ -34,23 +36,40 @@ pub struct InitPoolCtx<'info> {
         mint::token_program = token_program,
     )]
     mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer=payer,
+        associated_token::mint = reward_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    reward_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        associated_token::mint = mint,
+        associated_token::mint = reward_mint,
         associated_token::authority = payer,
         associated_token::token_program = token_program,
     )]
     payer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
-    #[account(mut, constraint = payer.key() == ADMIN @ErrorCode::InvalidAdmin)]
+    #[account(seeds = [CONFIG_PREFIX.as_bytes()], bump = config.bump)]
+    config: Box<Account<'info, Config>>,
+    #[account(mut, constraint = payer.key() == config.admin @ErrorCode::InvalidAdmin)]
     payer: Signer<'info>,
-    token_program: Program<'info, Token2022>,
+    token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<InitPoolCtx>, ix: InitPoolIx) -> Result<()> {
+pub fn init_pool_handler(ctx: Context<InitPoolCtx>, ix: InitPoolIx) -> Result<()> {
+    StakePool::check_stake_reward(ix.stake_reward)?;
+    let now = Clock::get()?.unix_timestamp;
     let bump = ctx.bumps.stake_pool;
     let new_stake_pool = StakePool {
+        version: STAKE_POOL_VERSION,
         bump,
         authority: ctx.accounts.payer.key(),
         total_staked: 0,
@@ -59,25 +78,56 @@ pub fn handler(ctx: Context<InitPoolCtx>, ix: InitPoolIx) -> Result<()> {
         stake_reward: ix.stake_reward,
         mint: ctx.accounts.mint.key(),
         identifier: ix.identifier,
+        reward_per_token_stored: 0,
+        last_update_time: now,
+        reward_reserve: ix.deposite_amount,
+        lock_tiers: Vec::new(),
+        reward_mint: ctx.accounts.reward_mint.key(),
+        early_unstake_penalty_bp: None,
+        penalty_treasury: None,
+        pending_authority: None,
+        operator: ctx.accounts.payer.key(),
+        paused: false,
+        rewards_start: now,
+        rewards_end: 0,
+        total_reward_budget: 0,
+        remaining_reward_budget: 0,
+        total_boosted_staked: 0,
+        unbonding_seconds: 0,
+        total_unbonding: 0,
+        compound_enabled: false,
+        receipt_mint: None,
+        max_total_staked: 0,
+        min_stake_amount: 0,
+        max_stake_per_wallet: 0,
+        param_change_delay: 0,
+        pending_params: None,
+        reward_curve: None,
     };
 
-    token_interface::transfer_checked(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token_interface::TransferChecked {
-                from: ctx.accounts.payer_token_account.to_account_info(),
-                mint: ctx.accounts.mint.to_account_info(),
-                to: ctx.accounts.pool_token_account.to_account_info(),
-                authority: ctx.accounts.payer.to_account_info(),
-            },
-        ),
+    transfer_in(
+        &ctx.accounts.token_program,
+        &ctx.accounts.reward_mint,
+        &ctx.accounts.payer_token_account,
+        &ctx.accounts.reward_vault,
+        &ctx.accounts.payer,
         ix.deposite_amount,
-        ctx.accounts.mint.decimals,
     )?;
 
     let stake_pool = &mut ctx.accounts.stake_pool;
 
     stake_pool.set_inner(new_stake_pool);
+
+    emit!(PoolCreated {
+        stake_pool: stake_pool.key(),
+        authority: stake_pool.authority,
+        mint: stake_pool.mint,
+        reward_mint: stake_pool.reward_mint,
+        stake_reward: stake_pool.stake_reward,
+        min_stake_seconds: ix.min_stake_seconds,
+        deposite_amount: ix.deposite_amount,
+        timestamp: stake_pool.last_update_time,
+    });
     Ok(())
 }
 
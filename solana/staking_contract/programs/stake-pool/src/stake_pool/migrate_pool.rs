@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::events::PoolMigrated;
+use crate::StakePool;
+
+#[derive(Accounts)]
+pub struct MigratePoolCtx<'info> {
+    /// CHECK: Stake pool account in any supported layout, see `StakePool::migrate_to_latest`
+    #[account(mut, owner = crate::ID)]
+    stake_pool: AccountInfo<'info>,
+    /// The V0 reward reserve is derived from this vault, it has to be the pool's ATA.
+    #[account(
+        associated_token::mint = stake_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    stake_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    token_program: Interface<'info, TokenInterface>,
+    system_program: Program<'info, System>,
+}
+
+pub fn migrate_pool_handler(ctx: Context<MigratePoolCtx>) -> Result<()> {
+    let stake_pool = StakePool::migrate_to_latest(
+        &ctx.accounts.stake_pool,
+        &ctx.accounts.pool_token_account,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+    require!(stake_pool.mint == ctx.accounts.stake_mint.key(), ErrorCode::InvalidStakeMint);
+
+    emit!(PoolMigrated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        version: stake_pool.version,
+        reward_reserve: stake_pool.reward_reserve,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-pub mod state;
+mod state;
 pub use state::*;
 
 pub mod init_pool;
@@ -12,3 +12,15 @@ pub use withdraw::*;
 
 pub mod deposite;
 pub use deposite::*;
+
+pub mod authority;
+pub use authority::*;
+
+pub mod params;
+pub use params::*;
+
+pub mod receipt;
+pub use receipt::*;
+
+pub mod migrate_pool;
+pub use migrate_pool::*;
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::{PoolParamsApplied, PoolParamsCancelled, PoolParamsProposed, RewardCurveUpdated};
+use crate::{PendingPoolParams, RewardCurve, StakePool, MAX_PARAM_CHANGE_DELAY};
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct PoolParamsCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_pool.authority == authority.key() @ErrorCode::InvalidAdmin,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Account<'info, StakePool>,
+    authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyPoolParamsCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Account<'info, StakePool>,
+}
+
+/// Queues a change of `stake_reward`, `min_stake_seconds`, `param_change_delay` and/or
+/// `reward_curve`.
+/// It can be applied once the current `param_change_delay` has elapsed and replaces
+/// any change that is still pending.
+pub fn propose_params_handler(
+    ctx: Context<PoolParamsCtx>,
+    stake_reward: Option<u64>,
+    min_stake_seconds: Option<u32>,
+    param_change_delay: Option<u32>,
+    reward_curve: Option<Option<RewardCurve>>,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    require!(
+        stake_reward.is_some()
+            || min_stake_seconds.is_some()
+            || param_change_delay.is_some()
+            || reward_curve.is_some(),
+        ErrorCode::InvalidInput
+    );
+    if let Some(stake_reward) = stake_reward {
+        stake_pool.check_stake_reward_change(stake_reward)?;
+    }
+    if let Some(min_stake_seconds) = min_stake_seconds {
+        require!(min_stake_seconds > 0, ErrorCode::InvalidInput);
+    }
+    if let Some(param_change_delay) = param_change_delay {
+        require!(param_change_delay <= MAX_PARAM_CHANGE_DELAY, ErrorCode::InvalidInput);
+    }
+    if let Some(Some(curve)) = reward_curve.as_ref() {
+        stake_pool.check_reward_curve_change(curve)?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let effective_at = now + i64::from(stake_pool.param_change_delay);
+    stake_pool.pending_params = Some(PendingPoolParams {
+        stake_reward,
+        min_stake_seconds,
+        param_change_delay,
+        reward_curve,
+        effective_at,
+    });
+
+    emit!(PoolParamsProposed {
+        stake_pool: stake_pool.key(),
+        stake_reward,
+        min_stake_seconds,
+        param_change_delay,
+        reward_curve,
+        effective_at,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+pub fn cancel_handler(ctx: Context<PoolParamsCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    require!(stake_pool.pending_params.is_some(), ErrorCode::NoPendingParams);
+    stake_pool.pending_params = None;
+
+    emit!(PoolParamsCancelled {
+        stake_pool: stake_pool.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Permissionless, anyone can apply a pending change once it is effective.
+pub fn apply_handler(ctx: Context<ApplyPoolParamsCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let pending = stake_pool.pending_params.ok_or(ErrorCode::NoPendingParams)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= pending.effective_at, ErrorCode::ParamsNotEffective);
+
+    // accrue at the old rate so the change only applies going forward
+    stake_pool.update_rewards(now)?;
+    if let Some(Some(curve)) = pending.reward_curve.as_ref() {
+        stake_pool.check_reward_curve_change(curve)?;
+    }
+    if let Some(stake_reward) = pending.stake_reward {
+        stake_pool.check_stake_reward_change(stake_reward)?;
+        stake_pool.stake_reward = stake_reward;
+    }
+    if let Some(min_stake_seconds) = pending.min_stake_seconds {
+        stake_pool.min_stake_seconds = Some(min_stake_seconds);
+    }
+    if let Some(param_change_delay) = pending.param_change_delay {
+        stake_pool.param_change_delay = param_change_delay;
+    }
+    if let Some(reward_curve) = pending.reward_curve {
+        stake_pool.reward_curve = reward_curve;
+    }
+    stake_pool.pending_params = None;
+
+    emit!(PoolParamsApplied {
+        stake_pool: stake_pool.key(),
+        stake_reward: stake_pool.stake_reward,
+        min_stake_seconds: stake_pool.min_stake_seconds,
+        param_change_delay: stake_pool.param_change_delay,
+        reward_curve: stake_pool.reward_curve,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Sets or removes (`None`) the reward curve right away. Timelocked pools have to
+/// go through `propose_pool_params` instead.
+pub fn curve_handler(ctx: Context<PoolParamsCtx>, reward_curve: Option<RewardCurve>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
+    if let Some(curve) = reward_curve.as_ref() {
+        stake_pool.check_reward_curve_change(curve)?;
+    }
+    // accrue at the old rate so the change only applies going forward
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    stake_pool.reward_curve = reward_curve;
+
+    emit!(RewardCurveUpdated {
+        stake_pool: stake_pool.key(),
+        reward_curve,
+        stake_reward: stake_pool.current_stake_reward()?,
+        timestamp: now,
+    });
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::events::ReceiptMintCreated;
+use crate::StakePool;
+use crate::RECEIPT_MINT_PREFIX;
+use crate::STAKE_POOL_PREFIX;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct InitReceiptMintCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_pool.authority == payer.key() @ErrorCode::InvalidAdmin,
+        constraint = stake_pool.receipt_mint.is_none() @ErrorCode::InvalidReceiptMint,
+        // existing positions would have no receipts to burn on unstake
+        constraint = stake_pool.total_stakers == 0 && stake_pool.total_unbonding == 0 @ErrorCode::PoolHasStakers,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [RECEIPT_MINT_PREFIX.as_bytes(), stake_pool.key().as_ref()],
+        bump,
+        mint::decimals = stake_mint.decimals,
+        mint::authority = stake_pool,
+        mint::token_program = token_program,
+    )]
+    receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint,
+        mint::token_program = token_program,
+    )]
+    stake_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    token_program: Interface<'info, TokenInterface>,
+    system_program: Program<'info, System>,
+}
+
+/// Enables liquid staking receipts for the pool. From now on every staked token is
+/// matched 1:1 by a receipt minted to the staker, and receipts are burned when
+/// principal leaves the position.
+/// Receipts are the claim on the stake: principal only leaves by burning receipts
+/// from the staker's account, and rewards are withheld while the staker holds fewer
+/// receipts than their stake in the pool, see `receipts_held`.
+pub fn receipt_handler(ctx: Context<InitReceiptMintCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.receipt_mint = Some(ctx.accounts.receipt_mint.key());
+
+    emit!(ReceiptMintCreated {
+        stake_pool: stake_pool.key(),
+        receipt_mint: ctx.accounts.receipt_mint.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Mints `amount` receipts to the staker when the pool has a receipt mint, no-op otherwise.
+pub fn mint_receipts<'info>(
+    stake_pool: &Account<'info, StakePool>,
+    receipt_mint: &Option<Box<InterfaceAccount<'info, Mint>>>,
+    to: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if stake_pool.receipt_mint.is_none() || amount == 0 {
+        return Ok(());
+    }
+    let receipt_mint = receipt_mint.as_ref().ok_or(ErrorCode::InvalidReceiptMint)?;
+    let to = to.as_ref().ok_or(ErrorCode::InvalidUserReceiptTokenAccount)?;
+
+    let seeds = &[
+        STAKE_POOL_PREFIX.as_bytes(),
+        stake_pool.identifier.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer = [&seeds[..]];
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            token_program,
+            token_interface::MintTo {
+                mint: receipt_mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: stake_pool.to_account_info(),
+            },
+            &signer,
+        ),
+        amount,
+    )
+}
+
+/// Whether `account` holds receipts for all of `staked_amount`, the stake of its owner
+/// in the pool. Always true when the pool has no receipt mint.
+pub fn receipts_held(
+    stake_pool: &StakePool,
+    account: &Option<Box<InterfaceAccount<TokenAccount>>>,
+    staked_amount: u64,
+) -> bool {
+    if stake_pool.receipt_mint.is_none() {
+        return true;
+    }
+    account
+        .as_ref()
+        .is_some_and(|account| account.amount >= staked_amount)
+}
+
+/// Burns `amount` receipts from the staker when the pool has a receipt mint, no-op otherwise.
+pub fn burn_receipts<'info>(
+    stake_pool: &Account<'info, StakePool>,
+    receipt_mint: &Option<Box<InterfaceAccount<'info, Mint>>>,
+    from: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    authority: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if stake_pool.receipt_mint.is_none() || amount == 0 {
+        return Ok(());
+    }
+    let receipt_mint = receipt_mint.as_ref().ok_or(ErrorCode::InvalidReceiptMint)?;
+    let from = from.as_ref().ok_or(ErrorCode::InvalidUserReceiptTokenAccount)?;
+
+    token_interface::burn(
+        CpiContext::new(
+            token_program,
+            token_interface::Burn {
+                mint: receipt_mint.to_account_info(),
+                from: from.to_account_info(),
+                authority,
+            },
+        ),
+        amount,
+    )
+}
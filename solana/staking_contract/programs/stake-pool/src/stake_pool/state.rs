@@ -1,9 +1,38 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::token_interface::TokenAccount;
+use crate::errors::ErrorCode;
+use crate::utils::transfer_sols;
 
-pub const STAKE_POOL_DEFAULT_SIZE: usize = 8 + 1 + 32 + 8 + 4 + 5 + 8 + 32 + 24 + 8;
+pub const STAKE_POOL_DEFAULT_SIZE: usize = 8 + StakePool::INIT_SPACE;
+pub const STAKE_POOL_VERSION: u8 = 3;
 pub const STAKE_POOL_PREFIX: &str = "stake-pool";
+pub const RECEIPT_MINT_PREFIX: &str = "receipt-mint";
+pub const SECONDS_IN_YEAR: u128 = 31536000;
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+pub const MAX_LOCK_TIERS: usize = 4;
+/// Pool and entry identifiers are PDA seeds, which are at most 32 bytes.
+pub const MAX_IDENTIFIER_LEN: usize = 32;
+pub const BASE_MULTIPLIER_BP: u16 = 10000;
+pub const MAX_MULTIPLIER_BP: u16 = 50000;
+/// Bounds of `stake_reward`, an APR in basis points.
+pub const MIN_STAKE_REWARD: u64 = 1;
+pub const MAX_STAKE_REWARD: u64 = 9999;
+/// A single change can at most multiply or divide `stake_reward` by this factor.
+pub const MAX_STAKE_REWARD_CHANGE: u64 = 2;
+/// Longest timelock a pool can put on parameter changes (30 days).
+pub const MAX_PARAM_CHANGE_DELAY: u32 = 2_592_000;
+
+/// Latest pool version: [`STAKE_POOL_VERSION`]
+/// Migration is explicit: an older layout can still deserialize into this struct
+/// (trailing fields read as zero or shifted), so every context checks
+/// `version == STAKE_POOL_VERSION` and fails with `UnsupportedAccountVersion`.
+/// After every layout bump each live pool has to go through `migrate_pool`
+/// (see `StakePool::migrate_to_latest`) before any other instruction accepts it.
 #[account]
+#[derive(InitSpace)]
 pub struct StakePool {
+    pub version: u8,
     pub bump: u8,
     pub authority: Pubkey,
     pub total_staked: u64,
@@ -11,5 +40,615 @@ pub struct StakePool {
     pub min_stake_seconds: Option<u32>,
     pub stake_reward: u64,
     pub mint: Pubkey,
+    #[max_len(MAX_IDENTIFIER_LEN)]
     pub identifier: String,
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    pub reward_reserve: u64,
+    #[max_len(MAX_LOCK_TIERS)]
+    pub lock_tiers: Vec<LockTier>,
+    pub reward_mint: Pubkey,
+    pub early_unstake_penalty_bp: Option<u16>,
+    pub penalty_treasury: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
+    pub operator: Pubkey,
+    pub paused: bool,
+    pub rewards_start: i64,
+    pub rewards_end: i64,
+    pub total_reward_budget: u64,
+    pub remaining_reward_budget: u64,
+    /// Sum of `amount * multiplier_bp` over all open positions.
+    pub total_boosted_staked: u128,
+    pub unbonding_seconds: u32,
+    pub total_unbonding: u64,
+    pub compound_enabled: bool,
+    /// Liquid staking receipt mint, see `init_receipt_mint`. Unstaking burns receipts
+    /// and rewards are only paid while the staker holds receipts for their stake.
+    pub receipt_mint: Option<Pubkey>,
+    /// Staking limits, 0 disables the corresponding check. `max_stake_per_wallet`
+    /// caps the stake a wallet holds over all its entries, see `StakerRegistry`.
+    pub max_total_staked: u64,
+    pub min_stake_amount: u64,
+    pub max_stake_per_wallet: u64,
+    /// Seconds a proposed parameter change waits before it can be applied. Settings
+    /// outside `PendingPoolParams` (campaign, lock tiers, unbonding, limits) can only
+    /// be changed directly while it is 0.
+    pub param_change_delay: u32,
+    pub pending_params: Option<PendingPoolParams>,
+    /// Utilization based APR replacing `stake_reward` while set.
+    pub reward_curve: Option<RewardCurve>,
+}
+
+/// Parameter change queued by `propose_pool_params`, applied by `apply_pool_params`
+/// once `effective_at` has passed. `None` fields are left unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub struct PendingPoolParams {
+    pub stake_reward: Option<u64>,
+    pub min_stake_seconds: Option<u32>,
+    pub param_change_delay: Option<u32>,
+    /// `Some(None)` removes the curve and goes back to `stake_reward`.
+    pub reward_curve: Option<Option<RewardCurve>>,
+    pub effective_at: i64,
+}
+
+/// Kinked APR curve, in basis points like `stake_reward`. Below `target_staked` the
+/// rate grows linearly from `base_reward` up to `base_reward + slope_reward` as
+/// `total_staked` goes to 0, at or above the target it stays at `base_reward`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub struct RewardCurve {
+    pub base_reward: u64,
+    pub slope_reward: u64,
+    pub target_staked: u64,
+}
+
+/// A lock period a staker can commit to in exchange for boosted rewards.
+/// `multiplier_bp` is applied on top of `stake_reward`, 10000 being 1x.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub struct LockTier {
+    pub lock_seconds: u32,
+    pub multiplier_bp: u16,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakePoolV2 {
+    pub version: u8,
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub total_staked: u64,
+    pub total_stakers: u32,
+    pub min_stake_seconds: Option<u32>,
+    pub stake_reward: u64,
+    pub mint: Pubkey,
+    #[max_len(MAX_IDENTIFIER_LEN)]
+    pub identifier: String,
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    pub reward_reserve: u64,
+    #[max_len(MAX_LOCK_TIERS)]
+    pub lock_tiers: Vec<LockTier>,
+    pub reward_mint: Pubkey,
+    pub early_unstake_penalty_bp: Option<u16>,
+    pub penalty_treasury: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
+    pub operator: Pubkey,
+    pub paused: bool,
+    pub rewards_start: i64,
+    pub rewards_end: i64,
+    pub total_reward_budget: u64,
+    pub remaining_reward_budget: u64,
+    pub total_boosted_staked: u128,
+    pub unbonding_seconds: u32,
+    pub total_unbonding: u64,
+    pub compound_enabled: bool,
+    pub receipt_mint: Option<Pubkey>,
+    pub max_total_staked: u64,
+    pub min_stake_amount: u64,
+    pub max_stake_per_wallet: u64,
+    pub param_change_delay: u32,
+    pub pending_params: Option<PendingPoolParamsV2>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq)]
+pub struct PendingPoolParamsV2 {
+    pub stake_reward: Option<u64>,
+    pub min_stake_seconds: Option<u32>,
+    pub param_change_delay: Option<u32>,
+    pub effective_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakePoolV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub total_staked: u64,
+    pub total_stakers: u32,
+    pub min_stake_seconds: Option<u32>,
+    pub stake_reward: u64,
+    pub mint: Pubkey,
+    #[max_len(MAX_IDENTIFIER_LEN)]
+    pub identifier: String,
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    pub reward_reserve: u64,
+    #[max_len(MAX_LOCK_TIERS)]
+    pub lock_tiers: Vec<LockTier>,
+    pub reward_mint: Pubkey,
+    pub early_unstake_penalty_bp: Option<u16>,
+    pub penalty_treasury: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
+    pub operator: Pubkey,
+    pub paused: bool,
+    pub rewards_start: i64,
+    pub rewards_end: i64,
+    pub total_reward_budget: u64,
+    pub remaining_reward_budget: u64,
+    pub total_boosted_staked: u128,
+    pub unbonding_seconds: u32,
+    pub total_unbonding: u64,
+    pub compound_enabled: bool,
+    pub receipt_mint: Option<Pubkey>,
+    pub max_total_staked: u64,
+    pub min_stake_amount: u64,
+    pub max_stake_per_wallet: u64,
+}
+
+#[account]
+pub struct StakePoolV0 {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub total_staked: u64,
+    pub total_stakers: u32,
+    pub min_stake_seconds: Option<u32>,
+    pub stake_reward: u64,
+    pub mint: Pubkey,
+    pub identifier: String,
+}
+
+impl StakePool {
+    /// Advances the reward-per-token accumulator up to `now` at the current reward rate,
+    /// see [`StakePool::current_stake_reward`].
+    /// Accrual only happens inside the `rewards_start..rewards_end` campaign window and
+    /// stops once `remaining_reward_budget` is spent when a budget is set.
+    /// Must be called before any change to `stake_reward` or to a staked amount.
+    pub fn update_rewards(&mut self, now: i64) -> Result<()> {
+        if now <= self.last_update_time {
+            return Ok(());
+        }
+        let from = self.last_update_time.max(self.rewards_start);
+        let to = if self.rewards_end > 0 { now.min(self.rewards_end) } else { now };
+        self.last_update_time = now;
+        if to <= from {
+            return Ok(());
+        }
+
+        let elapsed = u128::from((to - from) as u64);
+        // `total_staked` has not changed since `last_update_time`
+        let mut accrued = u128::from(self.current_stake_reward()?)
+            .checked_mul(REWARD_PRECISION)
+            .and_then(|f| f.checked_mul(elapsed))
+            .and_then(|f| f.checked_div(10000 * SECONDS_IN_YEAR))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        if self.total_reward_budget > 0 && self.total_boosted_staked > 0 {
+            let emitted = accrued
+                .checked_mul(self.total_boosted_staked)
+                .and_then(|f| f.checked_div(REWARD_PRECISION * 10000))
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            let remaining = u128::from(self.remaining_reward_budget);
+            if emitted > remaining {
+                accrued = remaining
+                    .checked_mul(REWARD_PRECISION * 10000)
+                    .and_then(|f| f.checked_div(self.total_boosted_staked))
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                self.remaining_reward_budget = 0;
+            } else {
+                self.remaining_reward_budget -= emitted as u64;
+            }
+        }
+
+        self.reward_per_token_stored = self
+            .reward_per_token_stored
+            .checked_add(accrued)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// APR in basis points paid right now: `stake_reward`, or the reward curve
+    /// evaluated at `total_staked` when one is set.
+    pub fn current_stake_reward(&self) -> Result<u64> {
+        let curve = match self.reward_curve {
+            Some(curve) => curve,
+            None => return Ok(self.stake_reward),
+        };
+        if self.total_staked >= curve.target_staked {
+            return Ok(curve.base_reward);
+        }
+        let bonus = u128::from(curve.slope_reward)
+            .checked_mul(u128::from(curve.target_staked - self.total_staked))
+            .and_then(|f| f.checked_div(u128::from(curve.target_staked)))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(curve.base_reward + bonus as u64)
+    }
+
+    /// The curve must have a target and stay within the `stake_reward` bounds.
+    pub fn check_reward_curve(curve: &RewardCurve) -> Result<()> {
+        require!(curve.target_staked > 0, ErrorCode::InvalidRewardCurve);
+        let max_reward = curve
+            .base_reward
+            .checked_add(curve.slope_reward)
+            .ok_or(ErrorCode::InvalidRewardCurve)?;
+        require!(
+            curve.base_reward >= MIN_STAKE_REWARD && max_reward <= MAX_STAKE_REWARD,
+            ErrorCode::InvalidRewardCurve
+        );
+        Ok(())
+    }
+
+    /// Checks `stake_reward` against [`MIN_STAKE_REWARD`] and [`MAX_STAKE_REWARD`].
+    pub fn check_stake_reward(stake_reward: u64) -> Result<()> {
+        require!(
+            (MIN_STAKE_REWARD..=MAX_STAKE_REWARD).contains(&stake_reward),
+            ErrorCode::InvalidStakeReward
+        );
+        Ok(())
+    }
+
+    /// Checks a change from the current `stake_reward` to `stake_reward`: it has to stay
+    /// within the absolute bounds and within [`MAX_STAKE_REWARD_CHANGE`] of the current rate.
+    pub fn check_stake_reward_change(&self, stake_reward: u64) -> Result<()> {
+        Self::check_stake_reward(stake_reward)?;
+        require!(
+            Self::within_reward_change(self.stake_reward, stake_reward),
+            ErrorCode::InvalidStakeReward
+        );
+        Ok(())
+    }
+
+    /// Checks a change to `curve`: besides [`Self::check_reward_curve`], both ends of the
+    /// curve have to stay within [`MAX_STAKE_REWARD_CHANGE`] of the current effective rate.
+    pub fn check_reward_curve_change(&self, curve: &RewardCurve) -> Result<()> {
+        Self::check_reward_curve(curve)?;
+        let current = self.current_stake_reward()?;
+        require!(
+            Self::within_reward_change(current, curve.base_reward)
+                && Self::within_reward_change(current, curve.base_reward + curve.slope_reward),
+            ErrorCode::InvalidRewardCurve
+        );
+        Ok(())
+    }
+
+    fn within_reward_change(current: u64, reward: u64) -> bool {
+        reward <= current.saturating_mul(MAX_STAKE_REWARD_CHANGE)
+            && reward.saturating_mul(MAX_STAKE_REWARD_CHANGE) >= current
+    }
+
+    /// Checks a new stake of `amount` by a wallet already staking `wallet_amount`
+    /// against the pool limits.
+    pub fn check_stake_limits(&self, wallet_amount: u64, amount: u64) -> Result<()> {
+        require!(amount >= self.min_stake_amount, ErrorCode::StakeBelowMinimum);
+        self.check_stake_caps(wallet_amount, amount)
+    }
+
+    /// Checks `amount` added to a wallet staking `wallet_amount` against the pool
+    /// and wallet caps, without the minimum stake.
+    pub fn check_stake_caps(&self, wallet_amount: u64, amount: u64) -> Result<()> {
+        if self.max_total_staked > 0 {
+            let total_staked = self
+                .total_staked
+                .checked_add(amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(total_staked <= self.max_total_staked, ErrorCode::PoolCapExceeded);
+        }
+        if self.max_stake_per_wallet > 0 {
+            let wallet_staked = wallet_amount
+                .checked_add(amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(wallet_staked <= self.max_stake_per_wallet, ErrorCode::WalletCapExceeded);
+        }
+        Ok(())
+    }
+
+    /// Adds a position's stake to the pool totals. Rewards must be updated first.
+    pub fn add_stake(&mut self, amount: u64, multiplier_bp: u16) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.total_boosted_staked = self
+            .total_boosted_staked
+            .checked_add(u128::from(amount) * u128::from(multiplier_bp))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Removes a position's stake from the pool totals. Rewards must be updated first.
+    pub fn remove_stake(&mut self, amount: u64, multiplier_bp: u16) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.total_boosted_staked = self
+            .total_boosted_staked
+            .checked_sub(u128::from(amount) * u128::from(multiplier_bp))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Takes up to `pending` rewards out of `reward_reserve` and returns the amount
+    /// that can be paid now. Whatever is not covered stays owed to the staker.
+    pub fn take_rewards(&mut self, pending: u64) -> u64 {
+        let payable = pending.min(self.reward_reserve);
+        self.reward_reserve -= payable;
+        payable
+    }
+}
+
+impl StakePool {
+    /// Migrates the pool in the provided account to the latest version, one layout
+    /// at a time, and returns it. Only called by `migrate_pool`. `payer` covers the
+    /// extra rent of the bigger layout.
+    pub fn migrate_to_latest<'info>(
+        stake_pool_info: &AccountInfo<'info>,
+        pool_token_account: &InterfaceAccount<'info, TokenAccount>,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<Box<Self>> {
+        let data = stake_pool_info.try_borrow_data()?;
+        let expected_v0_size = 8 + StakePoolV0::INIT_SPACE;
+        let data_size = data.len();
+        drop(data);
+
+        // Migrate to V1 if needed
+        if data_size == expected_v0_size {
+            msg!("Migrating stake pool account to V1");
+            Self::migrate_to_v1(stake_pool_info, pool_token_account, payer, system_program)?;
+        }
+
+        // Migrate to V2 if needed
+        let data = stake_pool_info.try_borrow_data()?;
+        let version = data[8];
+        drop(data);
+        if version == 1 {
+            msg!("Migrating stake pool account to V2");
+            Self::migrate_to_v2(stake_pool_info, payer, system_program)?;
+        }
+
+        // Migrate to V3 if needed
+        let data = stake_pool_info.try_borrow_data()?;
+        let version = data[8];
+        drop(data);
+        if version == 2 {
+            msg!("Migrating stake pool account to V3");
+            Self::migrate_to_v3(stake_pool_info, payer, system_program)?;
+        }
+
+        let data = stake_pool_info.try_borrow_data()?;
+        let stake_pool = Box::new(StakePool::try_deserialize(&mut &data[..])?);
+        require!(stake_pool.version == STAKE_POOL_VERSION, ErrorCode::UnsupportedAccountVersion);
+
+        Ok(stake_pool)
+    }
+
+    fn migrate_to_v1<'info>(
+        stake_pool_info: &AccountInfo<'info>,
+        pool_token_account: &InterfaceAccount<'info, TokenAccount>,
+        payer: &Signer<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let data = stake_pool_info.try_borrow_data()?;
+        require!(data[..8] == StakePool::DISCRIMINATOR, ErrorCode::UnsupportedAccountVersion);
+        let old_struct = StakePoolV0::deserialize(&mut &data[8..])?;
+        drop(data);
+
+        require!(pool_token_account.mint == old_struct.mint, ErrorCode::InvalidStakeMint);
+        // V0 pools paid rewards out of the staking vault, whatever is not staked is reward reserve
+        let reward_reserve = pool_token_account.amount.saturating_sub(old_struct.total_staked);
+        let now = Clock::get()?.unix_timestamp;
+
+        let new_struct = StakePoolV1 {
+            version: 1,
+            bump: old_struct.bump,
+            authority: old_struct.authority,
+            total_staked: old_struct.total_staked,
+            total_stakers: old_struct.total_stakers,
+            min_stake_seconds: old_struct.min_stake_seconds,
+            stake_reward: old_struct.stake_reward,
+            mint: old_struct.mint,
+            identifier: old_struct.identifier,
+            reward_per_token_stored: 0,
+            last_update_time: now,
+            reward_reserve,
+            lock_tiers: Vec::new(),
+            reward_mint: old_struct.mint,
+            early_unstake_penalty_bp: None,
+            penalty_treasury: None,
+            pending_authority: None,
+            operator: old_struct.authority,
+            paused: false,
+            rewards_start: now,
+            rewards_end: 0,
+            total_reward_budget: 0,
+            remaining_reward_budget: 0,
+            total_boosted_staked: u128::from(old_struct.total_staked) * u128::from(BASE_MULTIPLIER_BP),
+            unbonding_seconds: 0,
+            total_unbonding: 0,
+            compound_enabled: false,
+            receipt_mint: None,
+            max_total_staked: 0,
+            min_stake_amount: 0,
+            max_stake_per_wallet: 0,
+        };
+
+        let new_space = 8 + StakePoolV1::INIT_SPACE;
+        stake_pool_info.realloc(new_space, false)?;
+
+        let old_rent = Rent::get()?.minimum_balance(8 + StakePoolV0::INIT_SPACE);
+        let new_rent = Rent::get()?.minimum_balance(new_space);
+
+        if new_rent > old_rent {
+            let additional_lamports = new_rent
+                .checked_sub(old_rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            transfer_sols(payer, stake_pool_info, system_program, additional_lamports)?;
+        }
+
+        let mut account_data = stake_pool_info.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut account_data[8..]);
+
+        new_struct.serialize(&mut cursor)?;
+
+        Ok(())
+    }
+
+    fn migrate_to_v2<'info>(
+        stake_pool_info: &AccountInfo<'info>,
+        payer: &Signer<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let data = stake_pool_info.try_borrow_data()?;
+        require!(data[..8] == StakePool::DISCRIMINATOR, ErrorCode::UnsupportedAccountVersion);
+        let old_struct = StakePoolV1::deserialize(&mut &data[8..])?;
+        drop(data);
+
+        let new_struct = StakePoolV2 {
+            version: 2,
+            bump: old_struct.bump,
+            authority: old_struct.authority,
+            total_staked: old_struct.total_staked,
+            total_stakers: old_struct.total_stakers,
+            min_stake_seconds: old_struct.min_stake_seconds,
+            stake_reward: old_struct.stake_reward,
+            mint: old_struct.mint,
+            identifier: old_struct.identifier,
+            reward_per_token_stored: old_struct.reward_per_token_stored,
+            last_update_time: old_struct.last_update_time,
+            reward_reserve: old_struct.reward_reserve,
+            lock_tiers: old_struct.lock_tiers,
+            reward_mint: old_struct.reward_mint,
+            early_unstake_penalty_bp: old_struct.early_unstake_penalty_bp,
+            penalty_treasury: old_struct.penalty_treasury,
+            pending_authority: old_struct.pending_authority,
+            operator: old_struct.operator,
+            paused: old_struct.paused,
+            rewards_start: old_struct.rewards_start,
+            rewards_end: old_struct.rewards_end,
+            total_reward_budget: old_struct.total_reward_budget,
+            remaining_reward_budget: old_struct.remaining_reward_budget,
+            total_boosted_staked: old_struct.total_boosted_staked,
+            unbonding_seconds: old_struct.unbonding_seconds,
+            total_unbonding: old_struct.total_unbonding,
+            compound_enabled: old_struct.compound_enabled,
+            receipt_mint: old_struct.receipt_mint,
+            max_total_staked: old_struct.max_total_staked,
+            min_stake_amount: old_struct.min_stake_amount,
+            max_stake_per_wallet: old_struct.max_stake_per_wallet,
+            param_change_delay: 0,
+            pending_params: None,
+        };
+
+        let new_space = 8 + StakePoolV2::INIT_SPACE;
+        stake_pool_info.realloc(new_space, false)?;
+
+        let old_rent = Rent::get()?.minimum_balance(8 + StakePoolV1::INIT_SPACE);
+        let new_rent = Rent::get()?.minimum_balance(new_space);
+
+        if new_rent > old_rent {
+            let additional_lamports = new_rent
+                .checked_sub(old_rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            transfer_sols(payer, stake_pool_info, system_program, additional_lamports)?;
+        }
+
+        let mut account_data = stake_pool_info.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut account_data[8..]);
+
+        new_struct.serialize(&mut cursor)?;
+
+        Ok(())
+    }
+
+    fn migrate_to_v3<'info>(
+        stake_pool_info: &AccountInfo<'info>,
+        payer: &Signer<'info>,
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let data = stake_pool_info.try_borrow_data()?;
+        require!(data[..8] == StakePool::DISCRIMINATOR, ErrorCode::UnsupportedAccountVersion);
+        let old_struct = StakePoolV2::deserialize(&mut &data[8..])?;
+        drop(data);
+
+        let pending_params = old_struct.pending_params.map(|pending| PendingPoolParams {
+            stake_reward: pending.stake_reward,
+            min_stake_seconds: pending.min_stake_seconds,
+            param_change_delay: pending.param_change_delay,
+            reward_curve: None,
+            effective_at: pending.effective_at,
+        });
+        let new_struct = StakePool {
+            version: 3,
+            bump: old_struct.bump,
+            authority: old_struct.authority,
+            total_staked: old_struct.total_staked,
+            total_stakers: old_struct.total_stakers,
+            min_stake_seconds: old_struct.min_stake_seconds,
+            stake_reward: old_struct.stake_reward,
+            mint: old_struct.mint,
+            identifier: old_struct.identifier,
+            reward_per_token_stored: old_struct.reward_per_token_stored,
+            last_update_time: old_struct.last_update_time,
+            reward_reserve: old_struct.reward_reserve,
+            lock_tiers: old_struct.lock_tiers,
+            reward_mint: old_struct.reward_mint,
+            early_unstake_penalty_bp: old_struct.early_unstake_penalty_bp,
+            penalty_treasury: old_struct.penalty_treasury,
+            pending_authority: old_struct.pending_authority,
+            operator: old_struct.operator,
+            paused: old_struct.paused,
+            rewards_start: old_struct.rewards_start,
+            rewards_end: old_struct.rewards_end,
+            total_reward_budget: old_struct.total_reward_budget,
+            remaining_reward_budget: old_struct.remaining_reward_budget,
+            total_boosted_staked: old_struct.total_boosted_staked,
+            unbonding_seconds: old_struct.unbonding_seconds,
+            total_unbonding: old_struct.total_unbonding,
+            compound_enabled: old_struct.compound_enabled,
+            receipt_mint: old_struct.receipt_mint,
+            max_total_staked: old_struct.max_total_staked,
+            min_stake_amount: old_struct.min_stake_amount,
+            max_stake_per_wallet: old_struct.max_stake_per_wallet,
+            param_change_delay: old_struct.param_change_delay,
+            pending_params,
+            reward_curve: None,
+        };
+
+        let new_space = 8 + StakePool::INIT_SPACE;
+        stake_pool_info.realloc(new_space, false)?;
+
+        let old_rent = Rent::get()?.minimum_balance(8 + StakePoolV2::INIT_SPACE);
+        let new_rent = Rent::get()?.minimum_balance(new_space);
+
+        if new_rent > old_rent {
+            let additional_lamports = new_rent
+                .checked_sub(old_rent)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            transfer_sols(payer, stake_pool_info, system_program, additional_lamports)?;
+        }
+
+        let mut account_data = stake_pool_info.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut account_data[8..]);
+
+        new_struct.serialize(&mut cursor)?;
+
+        Ok(())
+    }
+}
+
+impl Space for StakePoolV0 {
+    const INIT_SPACE: usize = 1 + 32 + 8 + 4 + 5 + 8 + 32 + 24 + 8;
 }
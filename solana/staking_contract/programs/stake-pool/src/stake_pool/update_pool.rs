@@ -1,11 +1,34 @@
 use anchor_lang::prelude::*;
 use crate::errors::ErrorCode;
+use crate::events::{
+    CampaignUpdated, CompoundingUpdated, EarlyUnstakeConfigUpdated, LockTiersUpdated, MinStakeSecondsChanged, PoolPausedUpdated,
+    RewardRateChanged, StakeLimitsUpdated, UnbondingPeriodChanged,
+};
 use crate::StakePool;
+use crate::{LockTier, BASE_MULTIPLIER_BP, MAX_LOCK_TIERS, MAX_MULTIPLIER_BP};
+use crate::STAKE_POOL_VERSION;
+/// Operational settings, open to the pool authority and its operator.
 #[derive(Accounts)]
 pub struct UpdatePoolCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_pool.authority == payer.key()
+            || stake_pool.operator == payer.key() @ErrorCode::InvalidAdmin,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Account<'info, StakePool>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+/// Settings that move funds or change the pool economics, the operator cannot use them.
+#[derive(Accounts)]
+pub struct UpdatePoolAuthorityCtx<'info> {
     #[account(
         mut,
         constraint = stake_pool.authority == payer.key() @ErrorCode::InvalidAdmin,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
     )]
     stake_pool: Account<'info, StakePool>,
     #[account(mut)]
@@ -13,16 +36,188 @@ pub struct UpdatePoolCtx<'info> {
     system_program: Program<'info, System>,
 }
 
-pub fn reward_handler(ctx: Context<UpdatePoolCtx>, stake_reward: u64) -> Result<()> {
+pub fn reward_handler(ctx: Context<UpdatePoolAuthorityCtx>, stake_reward: u64) -> Result<()> {
     let stake_pool = &mut ctx.accounts.stake_pool;
-    require!(stake_reward > 0 && stake_reward < 10000, ErrorCode::InvalidInput);
+    // timelocked pools go through `propose_pool_params`
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
+    stake_pool.check_stake_reward_change(stake_reward)?;
+    // accrue at the old rate so the change only applies going forward
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    let old_stake_reward = stake_pool.stake_reward;
     stake_pool.stake_reward = stake_reward;
+
+    emit!(RewardRateChanged {
+        stake_pool: stake_pool.key(),
+        old_stake_reward,
+        new_stake_reward: stake_reward,
+        timestamp: now,
+    });
     Ok(())
 }
 
 pub fn time_handler(ctx: Context<UpdatePoolCtx>, min_stake_seconds: u32) -> Result<()> {
     let stake_pool = &mut ctx.accounts.stake_pool;
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
     require!(min_stake_seconds > 0, ErrorCode::InvalidInput);
     stake_pool.min_stake_seconds = Some(min_stake_seconds);
+
+    emit!(MinStakeSecondsChanged {
+        stake_pool: stake_pool.key(),
+        min_stake_seconds,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn lock_tiers_handler(ctx: Context<UpdatePoolCtx>, lock_tiers: Vec<LockTier>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
+    require!(lock_tiers.len() <= MAX_LOCK_TIERS, ErrorCode::InvalidLockTier);
+    for tier in lock_tiers.iter() {
+        require!(
+            tier.lock_seconds > 0
+                && tier.multiplier_bp >= BASE_MULTIPLIER_BP
+                && tier.multiplier_bp <= MAX_MULTIPLIER_BP,
+            ErrorCode::InvalidLockTier
+        );
+    }
+    // open positions keep the tier they were staked with
+    stake_pool.lock_tiers = lock_tiers;
+
+    emit!(LockTiersUpdated {
+        stake_pool: stake_pool.key(),
+        lock_tiers: stake_pool.lock_tiers.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn penalty_handler(
+    ctx: Context<UpdatePoolAuthorityCtx>,
+    penalty_bp: Option<u16>,
+    penalty_treasury: Option<Pubkey>,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    if let Some(penalty_bp) = penalty_bp {
+        require!(penalty_bp <= 10000, ErrorCode::InvalidInput);
+        // without a treasury the penalty is kept as reward reserve, which needs a single mint pool
+        require!(
+            penalty_treasury.is_some() || stake_pool.reward_mint == stake_pool.mint,
+            ErrorCode::InvalidPenaltyTreasury
+        );
+    }
+    stake_pool.early_unstake_penalty_bp = penalty_bp;
+    stake_pool.penalty_treasury = penalty_treasury;
+
+    emit!(EarlyUnstakeConfigUpdated {
+        stake_pool: stake_pool.key(),
+        penalty_bp,
+        penalty_treasury,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn paused_handler(ctx: Context<UpdatePoolCtx>, paused: bool) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.paused = paused;
+
+    emit!(PoolPausedUpdated {
+        stake_pool: stake_pool.key(),
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Starts a new reward campaign. `rewards_end` of 0 runs without an end date and a
+/// `total_reward_budget` of 0 leaves accrual uncapped.
+pub fn campaign_handler(
+    ctx: Context<UpdatePoolAuthorityCtx>,
+    rewards_start: i64,
+    rewards_end: i64,
+    total_reward_budget: u64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    // ending a campaign early cuts rewards as much as a rate change
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
+    require!(
+        rewards_end == 0 || rewards_end > rewards_start,
+        ErrorCode::InvalidRewardCampaign
+    );
+    // settle the previous campaign before its window and budget are replaced
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    stake_pool.rewards_start = rewards_start;
+    stake_pool.rewards_end = rewards_end;
+    stake_pool.total_reward_budget = total_reward_budget;
+    stake_pool.remaining_reward_budget = total_reward_budget;
+
+    emit!(CampaignUpdated {
+        stake_pool: stake_pool.key(),
+        rewards_start,
+        rewards_end,
+        total_reward_budget,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Only affects later unstakes, positions already unbonding keep their `withdrawable_at`.
+pub fn unbonding_handler(ctx: Context<UpdatePoolAuthorityCtx>, unbonding_seconds: u32) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    // a longer cooldown traps every later exit
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
+    stake_pool.unbonding_seconds = unbonding_seconds;
+
+    emit!(UnbondingPeriodChanged {
+        stake_pool: stake_pool.key(),
+        unbonding_seconds,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn compound_enabled_handler(ctx: Context<UpdatePoolCtx>, compound_enabled: bool) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    // rewards can only become principal when both are the same token
+    require!(
+        !compound_enabled || stake_pool.reward_mint == stake_pool.mint,
+        ErrorCode::InvalidRewardMint
+    );
+    stake_pool.compound_enabled = compound_enabled;
+
+    emit!(CompoundingUpdated {
+        stake_pool: stake_pool.key(),
+        compound_enabled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn limits_handler(
+    ctx: Context<UpdatePoolAuthorityCtx>,
+    max_total_staked: u64,
+    min_stake_amount: u64,
+    max_stake_per_wallet: u64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    require!(stake_pool.param_change_delay == 0, ErrorCode::ParamsTimelocked);
+    require!(
+        max_stake_per_wallet == 0 || max_stake_per_wallet >= min_stake_amount,
+        ErrorCode::InvalidInput
+    );
+    stake_pool.max_total_staked = max_total_staked;
+    stake_pool.min_stake_amount = min_stake_amount;
+    stake_pool.max_stake_per_wallet = max_stake_per_wallet;
+
+    emit!(StakeLimitsUpdated {
+        stake_pool: stake_pool.key(),
+        max_total_staked,
+        min_stake_amount,
+        max_stake_per_wallet,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
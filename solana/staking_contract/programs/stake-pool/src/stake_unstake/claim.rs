@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::fees::transfer_out;
+use crate::events::RewardsPaid;
+use crate::stake_pool::receipts_held;
+use crate::staker_registry::{StakerRegistry, STAKER_REGISTRY_PREFIX, STAKER_REGISTRY_SIZE};
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct ClaimRewardsCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_entry.stake_pool == stake_pool.key() @ErrorCode::InvalidStakePool,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    /// The wallet's stake, which its receipts must cover.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+
+    #[account(constraint = stake_pool.reward_mint == reward_mint.key() @ ErrorCode::InvalidRewardMint)]
+    reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(mut, constraint =
+        user_reward_account.mint == reward_mint.key()
+        && user_reward_account.owner == user.key()
+        @ ErrorCode::InvalidUserRewardMintTokenAccount
+    )]
+    user_reward_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(constraint =
+        Some(user_receipt_account.mint) == stake_pool.receipt_mint
+        && user_receipt_account.owner == user.key()
+        @ ErrorCode::InvalidUserReceiptTokenAccount
+    )]
+    user_receipt_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    token_program: Interface<'info, TokenInterface>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+/// Pays the entry's rewards. In a pool with receipts the staker must hold receipts
+/// for their whole stake, rewards of a sold position keep accruing but are withheld.
+pub fn claim_handler(ctx: Context<ClaimRewardsCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), ctx.accounts.user.key());
+    require!(
+        receipts_held(stake_pool, &ctx.accounts.user_receipt_account, staker_registry.staked_amount),
+        ErrorCode::ReceiptsNotHeld
+    );
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    stake_entry.settle_rewards(stake_pool.reward_per_token_stored)?;
+    if stake_entry.pending_rewards == 0 {
+        return err!(ErrorCode::NoRewardsToClaim);
+    }
+    let reward_amount = stake_pool.take_rewards(stake_entry.pending_rewards);
+    if reward_amount == 0 {
+        return err!(ErrorCode::InsufficientRewardReserve);
+    }
+
+    transfer_out(
+        &ctx.accounts.token_program,
+        &ctx.accounts.reward_mint,
+        &ctx.accounts.reward_vault,
+        &ctx.accounts.user_reward_account,
+        stake_pool,
+        reward_amount,
+    )?;
+
+    stake_entry.pending_rewards -= reward_amount;
+
+    emit!(RewardsPaid {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        amount: reward_amount,
+        pending_rewards: stake_entry.pending_rewards,
+        timestamp: now,
+    });
+
+    Ok(())
+}
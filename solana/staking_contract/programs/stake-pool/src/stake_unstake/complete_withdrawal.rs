@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::fees::transfer_out;
+use crate::events::WithdrawalCompleted;
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct CompleteWithdrawalCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_entry.stake_pool == stake_pool.key() @ErrorCode::InvalidStakePool,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+
+    #[account(constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint)]
+    stake_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(mut, constraint =
+        user_token_account.mint == stake_mint.key()
+        && user_token_account.owner == user.key()
+        @ ErrorCode::InvalidUserStakeMintTokenAccount
+    )]
+    user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    token_program: Interface<'info, TokenInterface>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+/// Releases principal queued by `unstake_tokens` once `withdrawable_at` has passed.
+pub fn complete_withdrawal_handler(ctx: Context<CompleteWithdrawalCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    let now = Clock::get()?.unix_timestamp;
+
+    let amount = stake_entry.unbonding_amount;
+    require!(amount > 0, ErrorCode::NothingToWithdraw);
+    require!(now >= stake_entry.withdrawable_at, ErrorCode::UnbondingNotOver);
+
+    transfer_out(
+        &ctx.accounts.token_program,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.pool_token_account,
+        &ctx.accounts.user_token_account,
+        stake_pool,
+        amount,
+    )?;
+
+    stake_pool.total_unbonding = stake_pool
+        .total_unbonding
+        .checked_sub(amount)
+        .expect("Sub error");
+    stake_entry.unbonding_amount = 0;
+
+    emit!(WithdrawalCompleted {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::staker_registry::{StakerRegistry, STAKER_REGISTRY_PREFIX, STAKER_REGISTRY_SIZE};
+use crate::events::Compounded;
+use crate::stake_pool::{mint_receipts, receipts_held};
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct CompoundCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_entry.stake_pool == stake_pool.key() @ErrorCode::InvalidStakePool,
+        constraint = stake_pool.compound_enabled @ErrorCode::CompoundingDisabled,
+        constraint = !stake_pool.paused @ErrorCode::PoolPaused,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(
+        mut,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    /// Tracks the wallet's stake for `max_stake_per_wallet`.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(mut, constraint = Some(receipt_mint.key()) == stake_pool.receipt_mint @ErrorCode::InvalidReceiptMint)]
+    receipt_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(mut, constraint =
+        Some(user_receipt_account.mint) == stake_pool.receipt_mint
+        && user_receipt_account.owner == user.key()
+        @ ErrorCode::InvalidUserReceiptTokenAccount
+    )]
+    user_receipt_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    token_program: Option<Interface<'info, TokenInterface>>,
+    system_program: Program<'info, System>,
+}
+
+/// Rolls pending rewards into the staked principal. Reward and stake tokens share
+/// the pool vault, so this only moves funds from `reward_reserve` to `total_staked`.
+pub fn compound_handler(ctx: Context<CompoundCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), ctx.accounts.user.key());
+    require!(stake_entry.amount > 0, ErrorCode::InvalidInput);
+    require!(
+        receipts_held(stake_pool, &ctx.accounts.user_receipt_account, staker_registry.staked_amount),
+        ErrorCode::ReceiptsNotHeld
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    stake_entry.settle_rewards(stake_pool.reward_per_token_stored)?;
+    if stake_entry.pending_rewards == 0 {
+        return err!(ErrorCode::NoRewardsToClaim);
+    }
+    let reward_amount = stake_pool.take_rewards(stake_entry.pending_rewards);
+    if reward_amount == 0 {
+        return err!(ErrorCode::InsufficientRewardReserve);
+    }
+
+    // compounded rewards count against the caps like any other stake
+    stake_pool.check_stake_caps(staker_registry.staked_amount, reward_amount)?;
+    stake_pool.add_stake(reward_amount, stake_entry.multiplier_bp)?;
+    stake_entry.record_increase(now);
+    staker_registry.add_stake(reward_amount)?;
+    stake_entry.amount = stake_entry
+        .amount
+        .checked_add(reward_amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    stake_entry.pending_rewards -= reward_amount;
+
+    if stake_pool.receipt_mint.is_some() {
+        let token_program = ctx
+            .accounts
+            .token_program
+            .as_ref()
+            .ok_or(ErrorCode::InvalidReceiptMint)?;
+        mint_receipts(
+            stake_pool,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.user_receipt_account,
+            token_program.to_account_info(),
+            reward_amount,
+        )?;
+    }
+
+    emit!(Compounded {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        amount: reward_amount,
+        entry_amount: stake_entry.amount,
+        total_staked: stake_pool.total_staked,
+        timestamp: now,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::staker_registry::{StakerRegistry, STAKER_REGISTRY_PREFIX, STAKER_REGISTRY_SIZE};
+use crate::fees::transfer_out;
+use crate::events::{EarlyUnstaked, UnbondingStarted};
+use crate::stake_pool::burn_receipts;
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct EarlyUnstakeTokenCtx<'info> {
+    #[account(
+        mut,
+        constraint = stake_entry.stake_pool == stake_pool.key() @ErrorCode::InvalidStakePool,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+
+    /// Tracks the wallet's stake for `max_stake_per_wallet`.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+
+    #[account(constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint)]
+    stake_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(mut, constraint =
+        user_token_account.mint == stake_mint.key()
+        && user_token_account.owner == user.key()
+        @ ErrorCode::InvalidUserStakeMintTokenAccount
+    )]
+    user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = Some(receipt_mint.key()) == stake_pool.receipt_mint @ErrorCode::InvalidReceiptMint)]
+    receipt_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(mut, constraint =
+        Some(user_receipt_account.mint) == stake_pool.receipt_mint
+        && user_receipt_account.owner == user.key()
+        @ ErrorCode::InvalidUserReceiptTokenAccount
+    )]
+    user_receipt_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut, constraint =
+        Some(treasury_token_account.key()) == stake_pool.penalty_treasury
+        && treasury_token_account.mint == stake_pool.mint
+        @ ErrorCode::InvalidPenaltyTreasury
+    )]
+    treasury_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    token_program: Interface<'info, TokenInterface>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+/// Exits a position before its lock expires. Rewards accrued since the entry was last
+/// settled are forfeited and `early_unstake_penalty_bp` of the principal is sent to
+/// the penalty treasury, or kept in the pool as reward reserve when no treasury is
+/// configured. Rewards already earned but deferred by a short reserve stay pending.
+/// Like `unstake_tokens`, the payout goes through the pool's unbonding period.
+/// An unvested grant stays staked, see `StakeEntry::withdrawable_amount`.
+pub fn early_unstake_handler(ctx: Context<EarlyUnstakeTokenCtx>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), stake_entry.staker);
+    let now = Clock::get()?.unix_timestamp;
+
+    let penalty_bp = stake_pool
+        .early_unstake_penalty_bp
+        .ok_or(ErrorCode::EarlyUnstakeDisabled)?;
+    require!(stake_entry.amount > 0, ErrorCode::InvalidInput);
+    require!(
+        stake_entry.is_locked(stake_pool.min_stake_seconds, now),
+        ErrorCode::StakeNotLocked
+    );
+
+    let deferred_rewards = stake_entry.pending_rewards;
+    stake_pool.update_rewards(now)?;
+    stake_entry.settle_rewards(stake_pool.reward_per_token_stored)?;
+
+    // an unvested grant stays staked
+    let amount = stake_entry.withdrawable_amount(now)?;
+    require!(amount > 0, ErrorCode::VestingLocked);
+    let penalty = u64::try_from(
+        u128::from(amount)
+            .checked_mul(u128::from(penalty_bp))
+            .and_then(|f| f.checked_div(10000))
+            .ok_or(ErrorCode::ArithmeticOverflow)?,
+    )
+    .map_err(|_| ErrorCode::ArithmeticOverflow)?;
+    let payout = amount.checked_sub(penalty).ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    burn_receipts(
+        stake_pool,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.user_receipt_account,
+        ctx.accounts.user.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        amount,
+    )?;
+
+    // FEATURE: Unbonding period, an early exit does not skip the cooldown
+    let unbonding = stake_pool.unbonding_seconds > 0;
+    if unbonding {
+        stake_entry.unbonding_amount = stake_entry
+            .unbonding_amount
+            .checked_add(payout)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        stake_entry.withdrawable_at = now
+            .checked_add(i64::from(stake_pool.unbonding_seconds))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        stake_pool.total_unbonding = stake_pool
+            .total_unbonding
+            .checked_add(payout)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    } else {
+        transfer_out(
+            &ctx.accounts.token_program,
+            &ctx.accounts.stake_mint,
+            &ctx.accounts.pool_token_account,
+            &ctx.accounts.user_token_account,
+            stake_pool,
+            payout,
+        )?;
+    }
+
+    if penalty > 0 {
+        if stake_pool.penalty_treasury.is_some() {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidPenaltyTreasury)?;
+            transfer_out(
+                &ctx.accounts.token_program,
+                &ctx.accounts.stake_mint,
+                &ctx.accounts.pool_token_account,
+                treasury_token_account,
+                stake_pool,
+                penalty,
+            )?;
+        } else {
+            stake_pool.reward_reserve = stake_pool
+                .reward_reserve
+                .checked_add(penalty)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+    }
+
+    stake_pool.remove_stake(amount, stake_entry.multiplier_bp)?;
+    staker_registry.remove_stake(amount);
+    stake_entry.amount -= amount;
+    stake_entry.record_decrease();
+    if stake_entry.amount == 0 {
+        stake_pool.total_stakers = stake_pool.total_stakers.checked_sub(1).expect("Sub error");
+        stake_entry.grant = None;
+    }
+    let forfeited_rewards = stake_entry.pending_rewards - deferred_rewards;
+    stake_entry.pending_rewards = deferred_rewards;
+
+    emit!(EarlyUnstaked {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        amount,
+        penalty,
+        forfeited_rewards,
+        total_staked: stake_pool.total_staked,
+        timestamp: now,
+    });
+    if unbonding {
+        emit!(UnbondingStarted {
+            stake_pool: stake_pool.key(),
+            stake_entry: stake_entry.key(),
+            staker: stake_entry.staker,
+            amount: payout,
+            unbonding_amount: stake_entry.unbonding_amount,
+            withdrawable_at: stake_entry.withdrawable_at,
+            timestamp: now,
+        });
+    }
+
+    Ok(())
+}
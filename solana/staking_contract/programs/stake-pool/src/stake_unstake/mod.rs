@@ -1,5 +1,23 @@
 pub mod stake;
 pub use stake::*;
 
+pub mod stake_for;
+pub use stake_for::*;
+
 pub mod unstake;
 pub use unstake::*;
+
+pub mod claim;
+pub use claim::*;
+
+pub mod early_unstake;
+pub use early_unstake::*;
+
+pub mod emergency_unstake;
+pub use emergency_unstake::*;
+
+pub mod complete_withdrawal;
+pub use complete_withdrawal::*;
+
+pub mod compound;
+pub use compound::*;
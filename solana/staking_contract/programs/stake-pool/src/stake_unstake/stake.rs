@@ -1,28 +1,25 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::{
-    token::Token, 
-    token_2022::{
-        Token2022,
-        spl_token_2022::{
-            self,
-            extension::{
-                transfer_fee::{TransferFeeConfig, MAX_FEE_BASIS_POINTS},
-                StateWithExtensions,
-            }
-        }
-    },
-    token_interface::{self, TokenAccount, Mint, spl_token_2022::extension::BaseStateWithExtensions},
-
-};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use crate::errors::ErrorCode;
+use crate::staker_registry::{StakerRegistry, STAKER_REGISTRY_PREFIX, STAKER_REGISTRY_SIZE};
+use crate::fees::transfer_in;
 use crate::stake_entry::StakeEntry;
-use crate::stake_pool::StakePool;
+use crate::events::Staked;
+use crate::stake_pool::{mint_receipts, StakePool};
+use crate::BASE_MULTIPLIER_BP;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
 
 #[derive(Accounts)]
-#[instruction(amount: u64)]
+#[instruction(amount: u64, lock_tier: Option<u8>)]
 pub struct StakeTokenCtx<'info> {
-    #[account(mut, constraint = stake_pool.key() == stake_entry.stake_pool @ErrorCode::InvalidStakePool)]
+    #[account(
+        mut,
+        constraint = stake_pool.key() == stake_entry.stake_pool @ErrorCode::InvalidStakePool,
+        constraint = !stake_pool.paused @ErrorCode::PoolPaused,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
     stake_pool: Box<Account<'info, StakePool>>,
     #[account(
         mut,
@@ -31,9 +28,23 @@ pub struct StakeTokenCtx<'info> {
         associated_token::token_program = token_program,
     )]
     pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
-    #[account(mut, constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner)]
+    #[account(
+        mut,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
     stake_entry: Box<Account<'info, StakeEntry>>,
 
+    /// Tracks the wallet's stake for `max_stake_per_wallet`.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+
     #[account(constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint)]
     stake_mint: Box<InterfaceAccount<'info, Mint>>,
 
@@ -46,79 +57,96 @@ pub struct StakeTokenCtx<'info> {
         @ ErrorCode::InvalidUserStakeMintTokenAccount
     )]
     user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = Some(receipt_mint.key()) == stake_pool.receipt_mint @ErrorCode::InvalidReceiptMint)]
+    receipt_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(mut, constraint =
+        Some(user_receipt_account.mint) == stake_pool.receipt_mint
+        && user_receipt_account.owner == user.key()
+        @ ErrorCode::InvalidUserReceiptTokenAccount
+    )]
+    user_receipt_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
-    token_program: Program<'info, Token2022>,
+    token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<StakeTokenCtx>, amount: u64) -> Result<()> {
+pub fn stake_handler(ctx: Context<StakeTokenCtx>, amount: u64, lock_tier: Option<u8>) -> Result<()> {
     let stake_pool = &mut ctx.accounts.stake_pool;
     let stake_entry = &mut ctx.accounts.stake_entry;
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), ctx.accounts.user.key());
 
-    if stake_entry.amount > 0 {
-        return err!(ErrorCode::TokensAlreadyStaked);
-    }
-
-    let transfer_amount = {
-        let transfer_fee = get_transfer_inverse_fee(&ctx.accounts.stake_mint.to_account_info(), amount)?;
+    require!(amount > 0, ErrorCode::InvalidInput);
+    stake_pool.check_stake_limits(staker_registry.staked_amount, amount)?;
 
-        amount.checked_add(transfer_fee).unwrap()
+    let (lock_seconds, multiplier_bp) = match lock_tier {
+        Some(index) => {
+            let tier = stake_pool
+                .lock_tiers
+                .get(usize::from(index))
+                .ok_or(ErrorCode::InvalidLockTier)?;
+            (tier.lock_seconds, tier.multiplier_bp)
+        }
+        None if stake_entry.amount > 0 => (stake_entry.lock_seconds, stake_entry.multiplier_bp),
+        None => (0, BASE_MULTIPLIER_BP),
     };
+    // a top-up keeps the tier the position was opened with
+    if stake_entry.amount > 0
+        && (lock_seconds != stake_entry.lock_seconds || multiplier_bp != stake_entry.multiplier_bp)
+    {
+        return err!(ErrorCode::LockTierMismatch);
+    }
 
-    token_interface::transfer_checked(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token_interface::TransferChecked {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                mint: ctx.accounts.stake_mint.to_account_info(),
-                to: ctx.accounts.pool_token_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
-        transfer_amount,
-        ctx.accounts.stake_mint.decimals,
+    // FEATURE: Token-2022 transfer fees are paid by the staker, see `crate::fees`
+    transfer_in(
+        &ctx.accounts.token_program,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.user_token_account,
+        &ctx.accounts.pool_token_account,
+        &ctx.accounts.user,
+        amount,
     )?;
 
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    stake_entry.settle_rewards(stake_pool.reward_per_token_stored)?;
+
     stake_entry.staker = ctx.accounts.user.key();
     stake_entry.stake_pool = stake_pool.key();
-    stake_entry.last_staked_at = Clock::get().unwrap().unix_timestamp;
+    stake_entry.record_increase(now);
+    stake_entry.last_staked_at = now;
+    stake_entry.lock_seconds = lock_seconds;
+    stake_entry.multiplier_bp = multiplier_bp;
+    if stake_entry.amount == 0 {
+        stake_pool.total_stakers = stake_pool.total_stakers.checked_add(1).unwrap();
+    }
     stake_entry.amount = stake_entry
         .amount
         .checked_add(amount)
         .unwrap();
-    stake_pool.total_stakers = stake_pool.total_stakers.checked_add(1).unwrap();
-    stake_pool.total_staked = stake_pool
-        .total_staked
-        .checked_add(amount)
-        .expect("Add error");
-
-    Ok(())
-}
+    stake_pool.add_stake(amount, multiplier_bp)?;
+    staker_registry.add_stake(amount)?;
 
-pub fn get_transfer_inverse_fee(mint_info: &AccountInfo, post_fee_amount: u64) -> Result<u64> {
-    if *mint_info.owner == Token::id() {
-        return Ok(0);
-    }
-    if post_fee_amount == 0 {
-        return err!(ErrorCode::InvalidInput);
-    }
-    let mint_data = mint_info.try_borrow_data()?;
-    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    mint_receipts(
+        stake_pool,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.user_receipt_account,
+        ctx.accounts.token_program.to_account_info(),
+        amount,
+    )?;
 
-    let fee = if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
-        let epoch = Clock::get()?.epoch;
+    emit!(Staked {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        amount,
+        entry_amount: stake_entry.amount,
+        lock_seconds,
+        multiplier_bp,
+        total_staked: stake_pool.total_staked,
+        timestamp: now,
+    });
 
-        let transfer_fee = transfer_fee_config.get_epoch_fee(epoch);
-        if u16::from(transfer_fee.transfer_fee_basis_points) == MAX_FEE_BASIS_POINTS {
-            u64::from(transfer_fee.maximum_fee)
-        } else {
-            transfer_fee_config
-                .calculate_inverse_epoch_fee(epoch, post_fee_amount)
-                .unwrap()
-        }
-    } else {
-        0
-    };
-    Ok(fee)
-}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::errors::ErrorCode;
+use crate::staker_registry::{StakerRegistry, STAKER_REGISTRY_PREFIX, STAKER_REGISTRY_SIZE};
+use crate::fees::transfer_in;
+use crate::stake_entry::{Grant, StakeEntry, GRANT_PREFIX, STAKE_ENTRY_SIZE, STAKE_ENTRY_VERSION};
+use crate::events::StakedFor;
+use crate::stake_pool::StakePool;
+use crate::BASE_MULTIPLIER_BP;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey, grant_id: u64)]
+pub struct StakeForCtx<'info> {
+    #[account(
+        mut,
+        constraint = !stake_pool.paused @ErrorCode::PoolPaused,
+        // receipts are transferable and would let the beneficiary sell unvested stake
+        constraint = stake_pool.receipt_mint.is_none() @ErrorCode::InvalidReceiptMint,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// A fresh entry for every grant, entries the beneficiary opened are never touched.
+    #[account(
+        init,
+        payer = funder,
+        space = STAKE_ENTRY_SIZE,
+        seeds = [
+            GRANT_PREFIX.as_bytes(),
+            stake_pool.key().as_ref(),
+            beneficiary.as_ref(),
+            funder.key().as_ref(),
+            grant_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+
+    /// Tracks the wallet's stake for `max_stake_per_wallet`.
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), beneficiary.as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+
+    #[account(constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint)]
+    stake_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    funder: Signer<'info>,
+    #[account(mut, constraint =
+        funder_token_account.amount > 0
+        && funder_token_account.mint == stake_mint.key()
+        && funder_token_account.owner == funder.key()
+        @ ErrorCode::InvalidUserStakeMintTokenAccount
+    )]
+    funder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    token_program: Interface<'info, TokenInterface>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    system_program: Program<'info, System>,
+}
+
+/// Opens a grant entry for `beneficiary` funded with the funder's tokens, the funder
+/// pays its rent. With `vesting_seconds > 0` the amount is recorded as a grant:
+/// nothing vests for `cliff_seconds`, then it vests linearly until `vesting_seconds`,
+/// and unvested tokens stay in the pool.
+#[allow(clippy::too_many_arguments)]
+pub fn stake_for_handler(
+    ctx: Context<StakeForCtx>,
+    beneficiary: Pubkey,
+    grant_id: u64,
+    amount: u64,
+    lock_tier: Option<u8>,
+    cliff_seconds: u32,
+    vesting_seconds: u32,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), beneficiary);
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(amount > 0, ErrorCode::InvalidInput);
+    require!(cliff_seconds <= vesting_seconds, ErrorCode::InvalidVestingSchedule);
+    stake_pool.check_stake_limits(staker_registry.staked_amount, amount)?;
+
+    let (lock_seconds, multiplier_bp) = match lock_tier {
+        Some(index) => {
+            let tier = stake_pool
+                .lock_tiers
+                .get(usize::from(index))
+                .ok_or(ErrorCode::InvalidLockTier)?;
+            (tier.lock_seconds, tier.multiplier_bp)
+        }
+        None => (0, BASE_MULTIPLIER_BP),
+    };
+
+    // the funder pays the Token-2022 transfer fee, see `crate::fees`
+    transfer_in(
+        &ctx.accounts.token_program,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.funder_token_account,
+        &ctx.accounts.pool_token_account,
+        &ctx.accounts.funder,
+        amount,
+    )?;
+
+    stake_pool.update_rewards(now)?;
+
+    stake_entry.version = STAKE_ENTRY_VERSION;
+    stake_entry.bump = ctx.bumps.stake_entry;
+    stake_entry.staker = beneficiary;
+    stake_entry.stake_pool = stake_pool.key();
+    stake_entry.identifier = stake_pool.identifier.clone();
+    stake_entry.reward_per_token_paid = stake_pool.reward_per_token_stored;
+    stake_entry.record_increase(now);
+    stake_entry.last_staked_at = now;
+    stake_entry.lock_seconds = lock_seconds;
+    stake_entry.multiplier_bp = multiplier_bp;
+    stake_entry.amount = amount;
+    stake_pool.total_stakers = stake_pool
+        .total_stakers
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    stake_pool.add_stake(amount, multiplier_bp)?;
+    staker_registry.add_stake(amount)?;
+
+    if vesting_seconds > 0 {
+        stake_entry.grant = Some(Grant {
+            funder: ctx.accounts.funder.key(),
+            amount,
+            start: now,
+            cliff: now + i64::from(cliff_seconds),
+            end: now + i64::from(vesting_seconds),
+        });
+    }
+
+    emit!(StakedFor {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        funder: ctx.accounts.funder.key(),
+        beneficiary,
+        grant_id,
+        amount,
+        lock_seconds,
+        multiplier_bp,
+        vesting_cliff: now + i64::from(cliff_seconds),
+        vesting_end: now + i64::from(vesting_seconds),
+        total_staked: stake_pool.total_staked,
+        timestamp: now,
+    });
+
+    Ok(())
+}
@@ -1,15 +1,24 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token_2022::Token2022;
-use anchor_spl::token_interface::{self, Mint, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use crate::errors::ErrorCode;
+use crate::staker_registry::{StakerRegistry, STAKER_REGISTRY_PREFIX, STAKER_REGISTRY_SIZE};
+use crate::fees::transfer_out;
+use crate::events::{EntryClosed, RewardsPaid, UnbondingStarted, Unstaked};
+use crate::stake_pool::{burn_receipts, receipts_held};
 use crate::StakeEntry;
 use crate::StakePool;
-use crate::STAKE_POOL_PREFIX;
+use crate::REWARD_PRECISION;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
 
 #[derive(Accounts)]
 pub struct UnstakeTokenCtx<'info> {
-    #[account(mut, constraint = stake_entry.stake_pool == stake_pool.key() @ErrorCode::InvalidStakePool)]
+    #[account(
+        mut,
+        constraint = stake_entry.stake_pool == stake_pool.key() @ErrorCode::InvalidStakePool,
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
     stake_pool: Box<Account<'info, StakePool>>,
     #[account(
         mut,
@@ -18,11 +27,34 @@ pub struct UnstakeTokenCtx<'info> {
         associated_token::token_program = token_program,
     )]
     pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
-    #[account(mut, constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner)]
+    #[account(
+        mut,
+        constraint = stake_entry.staker == user.key() @ErrorCode::InvalidStakeEntryOwner,
+        constraint = stake_entry.version == STAKE_ENTRY_VERSION @ErrorCode::UnsupportedAccountVersion,
+    )]
     stake_entry: Box<Account<'info, StakeEntry>>,
 
+    /// Tracks the wallet's stake for `max_stake_per_wallet`.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+
     #[account(constraint = stake_pool.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint)]
     stake_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = stake_pool,
+        associated_token::token_program = token_program,
+    )]
+    reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(constraint = stake_pool.reward_mint == reward_mint.key() @ ErrorCode::InvalidRewardMint)]
+    reward_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(mut)]
     user: Signer<'info>,
@@ -32,75 +64,195 @@ pub struct UnstakeTokenCtx<'info> {
         @ ErrorCode::InvalidUserStakeMintTokenAccount
     )]
     user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint =
+        user_reward_account.mint == reward_mint.key()
+        && user_reward_account.owner == user.key()
+        @ ErrorCode::InvalidUserRewardMintTokenAccount
+    )]
+    user_reward_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = Some(receipt_mint.key()) == stake_pool.receipt_mint @ErrorCode::InvalidReceiptMint)]
+    receipt_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(mut, constraint =
+        Some(user_receipt_account.mint) == stake_pool.receipt_mint
+        && user_receipt_account.owner == user.key()
+        @ ErrorCode::InvalidUserReceiptTokenAccount
+    )]
+    user_receipt_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
-    token_program: Program<'info, Token2022>,
+    token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<UnstakeTokenCtx>) -> Result<()> {
+pub fn unstake_handler(mut ctx: Context<UnstakeTokenCtx>, close_entry: bool) -> Result<()> {
+    let amount = ctx.accounts.stake_entry.amount;
+    // an empty entry would otherwise be counted out of `total_stakers` again
+    require!(amount > 0, ErrorCode::InvalidInput);
+    // unbonded principal stays on the entry until `complete_withdrawal`, so it
+    // cannot be closed here; close it with `close_entry` once withdrawn
+    require!(
+        !close_entry || ctx.accounts.stake_pool.unbonding_seconds == 0,
+        ErrorCode::EntryUnbonding
+    );
+    unstake(&mut ctx, amount)?;
+
+    // FEATURE: return the entry rent to the staker in the same transaction
+    if close_entry {
+        let stake_entry = &ctx.accounts.stake_entry;
+        require!(stake_entry.is_empty(), ErrorCode::StakeEntryNotEmpty);
+        emit!(EntryClosed {
+            stake_pool: stake_entry.stake_pool,
+            stake_entry: stake_entry.key(),
+            staker: stake_entry.staker,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        stake_entry.close(ctx.accounts.user.to_account_info())?;
+    }
+    Ok(())
+}
+
+pub fn partial_handler(mut ctx: Context<UnstakeTokenCtx>, amount: u64) -> Result<()> {
+    require!(
+        amount > 0 && amount <= ctx.accounts.stake_entry.amount,
+        ErrorCode::InvalidInput
+    );
+    unstake(&mut ctx, amount)
+}
+
+fn unstake(ctx: &mut Context<UnstakeTokenCtx>, amount: u64) -> Result<()> {
     let stake_pool = &mut ctx.accounts.stake_pool;
     let stake_entry = &mut ctx.accounts.stake_entry;
-    let seeds = &[
-        STAKE_POOL_PREFIX.as_bytes(),
-        stake_pool.identifier.as_ref(),
-        &[stake_pool.bump],
-    ];
-    let signer = [&seeds[..]];
-    // FEATURE: Minimum stake seconds
-    if stake_pool.min_stake_seconds.is_some()
-        && stake_pool.min_stake_seconds.unwrap() > 0
-        && ((Clock::get().unwrap().unix_timestamp - stake_entry.last_staked_at) as u32)
-            < stake_pool.min_stake_seconds.unwrap()
-    {
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), stake_entry.staker);
+    let now = Clock::get()?.unix_timestamp;
+    stake_pool.update_rewards(now)?;
+    stake_entry.settle_rewards(stake_pool.reward_per_token_stored)?;
+    // rewards the reserve cannot cover yet stay pending on the entry, as do rewards
+    // of a staker who no longer holds the receipts for their stake
+    let reward_amount = if receipts_held(stake_pool, &ctx.accounts.user_receipt_account, staker_registry.staked_amount) {
+        stake_pool.take_rewards(stake_entry.pending_rewards)
+    } else {
+        0
+    };
+
+    // FEATURE: Minimum stake seconds and lock tiers
+    if stake_entry.is_locked(stake_pool.min_stake_seconds, now) {
         return Err(error!(ErrorCode::MinStakeSecondsNotSatisfied));
     }
-    
-    let reward_amount: u64 = get_rewards(
-        u128::from(stake_entry.amount), 
-        u128::from(stake_pool.stake_reward), 
-        u128::from(stake_entry.last_staked_at as u64)
-    )?;
+    // FEATURE: granted stake only leaves the pool once vested
+    require!(amount <= stake_entry.withdrawable_amount(now)?, ErrorCode::VestingLocked);
+    if amount == stake_entry.amount {
+        stake_entry.grant = None;
+    }
 
-    token_interface::transfer_checked(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            token_interface::TransferChecked {
-                from: ctx.accounts.pool_token_account.to_account_info(),
-                mint: ctx.accounts.stake_mint.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
-                authority: stake_pool.to_account_info(),
-            },
-            &signer,
-        ),
-        stake_entry.amount + reward_amount,
-        ctx.accounts.stake_mint.decimals,
+    burn_receipts(
+        stake_pool,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.user_receipt_account,
+        ctx.accounts.user.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        amount,
     )?;
 
-    stake_entry.staker = Pubkey::default();
-    stake_pool.total_staked = stake_pool
-        .total_staked
-        .checked_sub(stake_entry.amount)
-        .expect("Sub error");
-    stake_pool.total_stakers = stake_pool.total_stakers.checked_sub(1).expect("Sub error");
-    stake_entry.amount = 0;
+    // FEATURE: Unbonding period, principal is released by complete_withdrawal.
+    // The entry keeps a single queue: unstaking again restarts the period for
+    // everything already unbonding.
+    let unbonding = stake_pool.unbonding_seconds > 0;
+    if unbonding {
+        stake_entry.unbonding_amount = stake_entry
+            .unbonding_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        stake_entry.withdrawable_at = now
+            .checked_add(i64::from(stake_pool.unbonding_seconds))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    } else {
+        transfer_out(
+            &ctx.accounts.token_program,
+            &ctx.accounts.stake_mint,
+            &ctx.accounts.pool_token_account,
+            &ctx.accounts.user_token_account,
+            stake_pool,
+            amount,
+        )?;
+    }
+
+    if reward_amount > 0 {
+        transfer_out(
+            &ctx.accounts.token_program,
+            &ctx.accounts.reward_mint,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.user_reward_account,
+            stake_pool,
+            reward_amount,
+        )?;
+    }
+
+    stake_pool.remove_stake(amount, stake_entry.multiplier_bp)?;
+    staker_registry.remove_stake(amount);
+    if unbonding {
+        stake_pool.total_unbonding = stake_pool
+            .total_unbonding
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    stake_entry.amount = stake_entry.amount.checked_sub(amount).expect("Sub error");
+    stake_entry.record_decrease();
+    if stake_entry.amount == 0 {
+        stake_pool.total_stakers = stake_pool.total_stakers.checked_sub(1).expect("Sub error");
+    }
+    stake_entry.pending_rewards -= reward_amount;
+
+    emit!(Unstaked {
+        stake_pool: stake_pool.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        amount,
+        entry_amount: stake_entry.amount,
+        total_staked: stake_pool.total_staked,
+        timestamp: now,
+    });
+    if unbonding {
+        emit!(UnbondingStarted {
+            stake_pool: stake_pool.key(),
+            stake_entry: stake_entry.key(),
+            staker: stake_entry.staker,
+            amount,
+            unbonding_amount: stake_entry.unbonding_amount,
+            withdrawable_at: stake_entry.withdrawable_at,
+            timestamp: now,
+        });
+    }
+    if reward_amount > 0 {
+        emit!(RewardsPaid {
+            stake_pool: stake_pool.key(),
+            stake_entry: stake_entry.key(),
+            staker: stake_entry.staker,
+            amount: reward_amount,
+            pending_rewards: stake_entry.pending_rewards,
+            timestamp: now,
+        });
+    }
 
     Ok(())
 }
 
+/// Rewards earned by `amount` staked tokens while the pool accumulator moved
+/// from `reward_per_token_paid` to `reward_per_token`, boosted by the entry's
+/// lock tier `multiplier_bp`.
 pub fn get_rewards(
     amount: u128,
-    stake_reward: u128,
-    last_staked_at: u128
+    reward_per_token: u128,
+    reward_per_token_paid: u128,
+    multiplier_bp: u128,
 ) -> Result<u64> {
+    let reward = reward_per_token
+        .checked_sub(reward_per_token_paid)
+        .and_then(|f| f.checked_mul(amount))
+        .and_then(|f| f.checked_div(REWARD_PRECISION))
+        .and_then(|f| f.checked_mul(multiplier_bp))
+        .and_then(|f| f.checked_div(10000))
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-    let seconds_in_year = 31536000 as u128;
-    let total_seconds_for_rewards = (Clock::get().unwrap().unix_timestamp as u128).checked_sub(last_staked_at).ok_or(ArithmeticOverflow)?;
-    let yearly_reward = amount.checked_mul(stake_reward).and_then(|f| f.checked_div(10000)).ok_or(ArithmeticOverflow)?;
-
-    let reward = yearly_reward.checked_mul(total_seconds_for_rewards).and_then(|f| f.checked_div(seconds_in_year)).ok_or(ArithmeticOverflow)?;
-
-    Ok(
-        u64::try_from(reward).unwrap()
-    ) 
-}  
\ No newline at end of file
+    Ok(u64::try_from(reward).map_err(|_| ErrorCode::ArithmeticOverflow)?)
+}
\ No newline at end of file
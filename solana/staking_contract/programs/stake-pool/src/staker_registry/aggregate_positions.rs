@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::PositionsSummary;
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::StakerRegistry;
+use crate::STAKER_REGISTRY_PREFIX;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct AggregatePositionsCtx<'info> {
+    #[account(
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), staker_registry.staker.as_ref()],
+        bump = staker_registry.bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+    #[account(constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion)]
+    stake_pool: Box<Account<'info, StakePool>>,
+}
+
+/// Sums positions `start..start + len` of the registry, `remaining_accounts` must
+/// hold them in order, closed positions are passed as their empty address. A staker
+/// with more positions than fit in one transaction is summed page by page, the
+/// partial summaries add up to the total.
+pub fn aggregate_positions_handler(ctx: Context<AggregatePositionsCtx>, start: u32, len: u32) -> Result<PositionsSummary> {
+    let staker_registry = &ctx.accounts.staker_registry;
+    let end = start.checked_add(len).ok_or(ErrorCode::ArithmeticOverflow)?;
+    require!(
+        len > 0 && end <= staker_registry.position_count && ctx.remaining_accounts.len() == len as usize,
+        ErrorCode::InvalidPositionAccounts
+    );
+
+    // rewards are accrued on a copy, this instruction does not write the pool
+    let now = Clock::get()?.unix_timestamp;
+    let mut stake_pool = StakePool::clone(&ctx.accounts.stake_pool);
+    stake_pool.update_rewards(now)?;
+
+    let mut summary = PositionsSummary::default();
+    for (position, account) in (start..end).zip(ctx.remaining_accounts.iter()) {
+        // closed positions have no stored bump and pay for the full derivation
+        if account.data_is_empty() {
+            let address = StakerRegistry::position_address(
+                &staker_registry.stake_pool,
+                &staker_registry.staker,
+                position,
+            );
+            require!(account.key() == address, ErrorCode::InvalidPositionAccounts);
+            continue;
+        }
+
+        require!(account.owner == &crate::ID, ErrorCode::InvalidPositionAccounts);
+        let mut stake_entry = StakeEntry::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require!(stake_entry.version == STAKE_ENTRY_VERSION, ErrorCode::UnsupportedAccountVersion);
+        let address = StakerRegistry::position_address_with_bump(
+            &staker_registry.stake_pool,
+            &staker_registry.staker,
+            position,
+            stake_entry.bump,
+        )?;
+        require!(account.key() == address, ErrorCode::InvalidPositionAccounts);
+        stake_entry.settle_rewards(stake_pool.reward_per_token_stored)?;
+        if stake_entry.is_empty() {
+            continue;
+        }
+        summary.open_positions += 1;
+        summary.amount = summary
+            .amount
+            .checked_add(stake_entry.amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        if stake_entry.is_locked(stake_pool.min_stake_seconds, now) {
+            summary.locked_amount = summary
+                .locked_amount
+                .checked_add(stake_entry.amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+        summary.pending_rewards = summary
+            .pending_rewards
+            .checked_add(stake_entry.pending_rewards)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        summary.unbonding_amount = summary
+            .unbonding_amount
+            .checked_add(stake_entry.unbonding_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,8 @@
+mod state;
+pub use state::*;
+
+pub mod open_position;
+pub use open_position::*;
+
+pub mod aggregate_positions;
+pub use aggregate_positions::*;
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::PositionOpened;
+use crate::StakeEntry;
+use crate::StakePool;
+use crate::StakerRegistry;
+use crate::BASE_MULTIPLIER_BP;
+use crate::POSITION_PREFIX;
+use crate::STAKER_REGISTRY_PREFIX;
+use crate::STAKER_REGISTRY_SIZE;
+use crate::STAKE_ENTRY_SIZE;
+use crate::STAKE_ENTRY_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct OpenPositionCtx<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = STAKER_REGISTRY_SIZE,
+        seeds = [STAKER_REGISTRY_PREFIX.as_bytes(), stake_pool.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    staker_registry: Box<Account<'info, StakerRegistry>>,
+    #[account(
+        init,
+        payer = payer,
+        space = STAKE_ENTRY_SIZE,
+        seeds = [
+            POSITION_PREFIX.as_bytes(),
+            stake_pool.key().as_ref(),
+            payer.key().as_ref(),
+            staker_registry.position_count.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    #[account(constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion)]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+pub fn open_position_handler(ctx: Context<OpenPositionCtx>) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    let staker_registry = &mut ctx.accounts.staker_registry;
+    staker_registry.init_if_needed(ctx.bumps.staker_registry, stake_pool.key(), ctx.accounts.payer.key());
+    let position = staker_registry.position_count;
+    staker_registry.position_count = position
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    // a position is a regular entry, its lock starts with its own first stake
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    stake_entry.version = STAKE_ENTRY_VERSION;
+    stake_entry.bump = ctx.bumps.stake_entry;
+    stake_entry.stake_pool = stake_pool.key();
+    stake_entry.staker = ctx.accounts.payer.key();
+    stake_entry.amount = 0;
+    stake_entry.multiplier_bp = BASE_MULTIPLIER_BP;
+    stake_entry.identifier = stake_pool.identifier.clone();
+
+    emit!(PositionOpened {
+        stake_pool: stake_pool.key(),
+        staker_registry: staker_registry.key(),
+        stake_entry: stake_entry.key(),
+        staker: stake_entry.staker,
+        position,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
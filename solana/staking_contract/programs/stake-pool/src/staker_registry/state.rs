@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+
+pub const STAKER_REGISTRY_PREFIX: &str = "staker-registry";
+pub const STAKER_REGISTRY_SIZE: usize = 8 + StakerRegistry::INIT_SPACE;
+pub const STAKER_REGISTRY_VERSION: u8 = 1;
+pub const POSITION_PREFIX: &str = "position";
+
+/// Per-wallet state of a staker in a pool, at seeds `[STAKER_REGISTRY_PREFIX, stake_pool, staker]`.
+/// Position `n` is a `StakeEntry` at seeds `[POSITION_PREFIX, stake_pool, staker, n]`,
+/// indexes are never reused.
+#[account]
+#[derive(InitSpace)]
+pub struct StakerRegistry {
+    pub version: u8,
+    pub bump: u8,
+    pub staker: Pubkey,
+    pub stake_pool: Pubkey,
+    pub position_count: u32,
+    /// Principal staked by the wallet over all its entries, positions and grants,
+    /// checked against `StakePool::max_stake_per_wallet`. Entries migrated from
+    /// before the registry existed are not counted.
+    pub staked_amount: u64,
+}
+
+/// Totals over a range of positions of a registry, returned by `aggregate_positions`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct PositionsSummary {
+    /// Positions still holding stake, unbonding tokens or unpaid rewards.
+    pub open_positions: u32,
+    pub amount: u64,
+    pub locked_amount: u64,
+    pub pending_rewards: u64,
+    pub unbonding_amount: u64,
+}
+
+impl StakerRegistry {
+    /// Fills in a registry created by `init_if_needed`, a no-op once initialized.
+    pub fn init_if_needed(&mut self, bump: u8, stake_pool: Pubkey, staker: Pubkey) {
+        if self.version == 0 {
+            self.version = STAKER_REGISTRY_VERSION;
+            self.bump = bump;
+            self.staker = staker;
+            self.stake_pool = stake_pool;
+        }
+    }
+
+    pub fn add_stake(&mut self, amount: u64) -> Result<()> {
+        self.staked_amount = self
+            .staked_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Saturates, stake of migrated entries was never added.
+    pub fn remove_stake(&mut self, amount: u64) {
+        self.staked_amount = self.staked_amount.saturating_sub(amount);
+    }
+
+    pub fn position_address(stake_pool: &Pubkey, staker: &Pubkey, position: u32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                POSITION_PREFIX.as_bytes(),
+                stake_pool.as_ref(),
+                staker.as_ref(),
+                &position.to_le_bytes(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    /// Address of `position` from its stored `bump`, a single hash instead of the
+    /// bump search of `position_address`.
+    pub fn position_address_with_bump(stake_pool: &Pubkey, staker: &Pubkey, position: u32, bump: u8) -> Result<Pubkey> {
+        Pubkey::create_program_address(
+            &[
+                POSITION_PREFIX.as_bytes(),
+                stake_pool.as_ref(),
+                staker.as_ref(),
+                &position.to_le_bytes(),
+                &[bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidPositionAccounts))
+    }
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+
+pub fn transfer_sols<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let transfer_ix = system_instruction::transfer(&from.key(), &to.key(), amount);
+
+    invoke(
+        &transfer_ix,
+        &[
+            from.to_account_info(),
+            to.to_account_info(),
+            system_program.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::voting::sum_voting_power;
+use crate::StakePool;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+pub struct GetVotingPowerCtx<'info> {
+    #[account(constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion)]
+    stake_pool: Box<Account<'info, StakePool>>,
+}
+
+/// Returns the current voting power of `staker` over the entries passed as remaining accounts.
+pub fn get_voting_power_handler(ctx: Context<GetVotingPowerCtx>, staker: Pubkey) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    sum_voting_power(&ctx.accounts.stake_pool.key(), &staker, ctx.remaining_accounts, now)
+}
@@ -0,0 +1,8 @@
+mod state;
+pub use state::*;
+
+pub mod get_voting_power;
+pub use get_voting_power::*;
+
+pub mod snapshot;
+pub use snapshot::*;
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::events::VotingPowerSnapshot;
+use crate::voting::{sum_voting_power, total_voting_power};
+use crate::StakePool;
+use crate::VotingCheckpoint;
+use crate::VOTING_CHECKPOINT_PREFIX;
+use crate::VOTING_CHECKPOINT_SIZE;
+use crate::VOTING_CHECKPOINT_VERSION;
+use crate::STAKE_POOL_VERSION;
+
+#[derive(Accounts)]
+#[instruction(snapshot_at: i64)]
+pub struct SnapshotVotingPowerCtx<'info> {
+    #[account(
+        init,
+        payer = staker,
+        space = VOTING_CHECKPOINT_SIZE,
+        seeds = [
+            VOTING_CHECKPOINT_PREFIX.as_bytes(),
+            stake_pool.key().as_ref(),
+            staker.key().as_ref(),
+            snapshot_at.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    voting_checkpoint: Box<Account<'info, VotingCheckpoint>>,
+    #[account(constraint = stake_pool.version == STAKE_POOL_VERSION @ErrorCode::UnsupportedAccountVersion)]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    staker: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+/// Records the staker's voting power at `snapshot_at` over the entries passed as
+/// remaining accounts. `snapshot_at` is chosen by governance and must have passed:
+/// stake added after it does not count, so tokens unstaked and restaked from another
+/// wallet once the snapshot point is known cannot vote twice.
+pub fn snapshot_handler(ctx: Context<SnapshotVotingPowerCtx>, snapshot_at: i64) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(snapshot_at <= clock.unix_timestamp, ErrorCode::InvalidSnapshotTime);
+
+    let stake_pool = &ctx.accounts.stake_pool;
+    let staker = ctx.accounts.staker.key();
+    let voting_checkpoint = &mut ctx.accounts.voting_checkpoint;
+    voting_checkpoint.version = VOTING_CHECKPOINT_VERSION;
+    voting_checkpoint.bump = ctx.bumps.voting_checkpoint;
+    voting_checkpoint.stake_pool = stake_pool.key();
+    voting_checkpoint.staker = staker;
+    voting_checkpoint.slot = clock.slot;
+    voting_checkpoint.snapshot_at = snapshot_at;
+    voting_checkpoint.voting_power =
+        sum_voting_power(&stake_pool.key(), &staker, ctx.remaining_accounts, snapshot_at)?;
+    voting_checkpoint.total_voting_power = total_voting_power(stake_pool.total_boosted_staked)?;
+
+    emit!(VotingPowerSnapshot {
+        stake_pool: stake_pool.key(),
+        voting_checkpoint: voting_checkpoint.key(),
+        staker,
+        slot: clock.slot,
+        snapshot_at,
+        voting_power: voting_checkpoint.voting_power,
+        total_voting_power: voting_checkpoint.total_voting_power,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
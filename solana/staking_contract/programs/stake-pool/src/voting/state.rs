@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::StakeEntry;
+use crate::BASE_MULTIPLIER_BP;
+use crate::STAKE_ENTRY_VERSION;
+
+pub const VOTING_CHECKPOINT_PREFIX: &str = "voting-checkpoint";
+pub const VOTING_CHECKPOINT_SIZE: usize = 8 + VotingCheckpoint::INIT_SPACE;
+pub const VOTING_CHECKPOINT_VERSION: u8 = 1;
+
+/// Voting power of a staker in a pool as of the governance chosen `snapshot_at`, written
+/// once by `snapshot_voting_power` at seeds `[VOTING_CHECKPOINT_PREFIX, stake_pool, staker, snapshot_at]`.
+#[account]
+#[derive(InitSpace)]
+pub struct VotingCheckpoint {
+    pub version: u8,
+    pub bump: u8,
+    pub stake_pool: Pubkey,
+    pub staker: Pubkey,
+    /// Slot the checkpoint was written at.
+    pub slot: u64,
+    pub snapshot_at: i64,
+    pub voting_power: u64,
+    /// Voting power of the whole pool at the time of the snapshot, with undecayed
+    /// lock boosts. An entry never votes more at `snapshot_at` than its current
+    /// boosted stake, so this bounds the sum of all stakers' `voting_power`.
+    pub total_voting_power: u64,
+}
+
+impl StakeEntry {
+    /// Records that `amount` is about to grow at `now`, for `voting_power`.
+    pub fn record_increase(&mut self, now: i64) {
+        // several increases in the same second keep the amount held before the first
+        if now > self.amount_increased_at {
+            self.prior_amount = self.amount;
+            self.prior_amount_since = self.amount_increased_at;
+            self.amount_increased_at = now;
+        }
+    }
+
+    /// Records that `amount` has shrunk, withdrawals count against every point in
+    /// the history.
+    pub fn record_decrease(&mut self) {
+        self.prior_amount = self.prior_amount.min(self.amount);
+    }
+
+    /// Lowest amount staked from `at` until now, weighted by the lock tier multiplier,
+    /// tokens unbonding do not vote. The boost above 1x decays linearly with the lock
+    /// time remaining at `at`. Stake added after `at` does not count. The entry only
+    /// remembers the amount before its last increase: once it grew twice after `at`
+    /// it has no voting power at `at`.
+    pub fn voting_power(&self, at: i64) -> Result<u64> {
+        let (amount, boosted) = if at >= self.amount_increased_at {
+            (self.amount, true)
+        } else if at >= self.prior_amount_since {
+            // a top-up restarted the lock after `at`, the earlier lock is unknown
+            (self.prior_amount, self.last_staked_at <= at)
+        } else {
+            return Ok(0);
+        };
+        let base = u128::from(BASE_MULTIPLIER_BP);
+        let boost = if boosted {
+            u128::from(self.multiplier_bp.saturating_sub(BASE_MULTIPLIER_BP))
+        } else {
+            0
+        };
+        let lock_seconds = u128::from(self.lock_seconds);
+        let (weight, scale) = if lock_seconds == 0 {
+            (base + boost, base)
+        } else {
+            let lock_end = self.last_staked_at.saturating_add(i64::from(self.lock_seconds));
+            let remaining = u128::from(lock_end.saturating_sub(at).max(0) as u64);
+            (base * lock_seconds + boost * remaining, base * lock_seconds)
+        };
+        let voting_power = u128::from(amount)
+            .checked_mul(weight)
+            .and_then(|f| f.checked_div(scale))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(u64::try_from(voting_power).map_err(|_| ErrorCode::ArithmeticOverflow)?)
+    }
+}
+
+/// Sums the voting power at `at` of `entries`, which must be distinct entries of `staker` in `stake_pool`.
+pub fn sum_voting_power(stake_pool: &Pubkey, staker: &Pubkey, entries: &[AccountInfo], at: i64) -> Result<u64> {
+    let mut voting_power: u64 = 0;
+    for (index, account) in entries.iter().enumerate() {
+        require!(
+            account.owner == &crate::ID && !entries[..index].iter().any(|other| other.key == account.key),
+            ErrorCode::InvalidVotingEntries
+        );
+        let stake_entry = StakeEntry::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require!(stake_entry.version == STAKE_ENTRY_VERSION, ErrorCode::UnsupportedAccountVersion);
+        require!(
+            stake_entry.stake_pool == *stake_pool && stake_entry.staker == *staker,
+            ErrorCode::InvalidVotingEntries
+        );
+        voting_power = voting_power
+            .checked_add(stake_entry.voting_power(at)?)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    Ok(voting_power)
+}
+
+/// Voting power of all positions in the pool at their full lock boost.
+pub fn total_voting_power(total_boosted_staked: u128) -> Result<u64> {
+    let total_voting_power = total_boosted_staked / u128::from(BASE_MULTIPLIER_BP);
+    Ok(u64::try_from(total_voting_power).map_err(|_| ErrorCode::ArithmeticOverflow)?)
+}
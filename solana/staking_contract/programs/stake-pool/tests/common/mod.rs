@@ -0,0 +1,1189 @@
+#![allow(dead_code)]
+
+use anchor_lang::{
+    AccountDeserialize, AccountSerialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+};
+use spl_token_2022::extension::{
+    transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType, StateWithExtensions,
+};
+use stake_pool::errors::ErrorCode;
+use stake_pool::{
+    Config, InitPoolIx, LockTier, PositionsSummary, StakeEntry, StakePool, StakerRegistry,
+    CONFIG_PREFIX, RECEIPT_MINT_PREFIX, STAKER_REGISTRY_PREFIX, STAKE_ENTRY_PREFIX,
+    STAKE_POOL_PREFIX,
+};
+
+pub const DECIMALS: u8 = 6;
+pub const SECONDS_IN_YEAR: i64 = 31_536_000;
+pub const STAKE_REWARD: u64 = 1000;
+pub const REWARD_DEPOSIT: u64 = 1_000_000_000;
+pub const STAKE_AMOUNT: u64 = 10_000_000;
+
+/// Anchor's `entry` ties the account slice and the account infos to one lifetime,
+/// `processor!` expects them to be independent.
+fn process_instruction<'a, 'b, 'c, 'd>(
+    program_id: &'a Pubkey,
+    accounts: &'b [AccountInfo<'c>],
+    data: &'d [u8],
+) -> ProgramResult {
+    let accounts: &'c [AccountInfo<'c>] = unsafe { std::mem::transmute(accounts) };
+    stake_pool::entry(program_id, accounts, data)
+}
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_PREFIX.as_bytes()], &stake_pool::ID).0
+}
+
+pub fn pool_address(identifier: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[STAKE_POOL_PREFIX.as_bytes(), identifier.as_bytes()],
+        &stake_pool::ID,
+    )
+    .0
+}
+
+pub fn entry_address(identifier: &str, stake_pool: &Pubkey, staker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STAKE_ENTRY_PREFIX.as_bytes(),
+            identifier.as_bytes(),
+            stake_pool.as_ref(),
+            staker.as_ref(),
+        ],
+        &stake_pool::ID,
+    )
+    .0
+}
+
+/// Reward the program owes for `amount` staked `elapsed` seconds at `stake_reward` bp APR,
+/// following the same rounding as the on-chain accumulator.
+pub fn expected_rewards(amount: u64, stake_reward: u64, elapsed: i64, multiplier_bp: u16) -> u64 {
+    let reward_per_token =
+        u128::from(stake_reward) * stake_pool::REWARD_PRECISION * elapsed as u128
+            / (10000 * stake_pool::SECONDS_IN_YEAR);
+    (reward_per_token * u128::from(amount) / stake_pool::REWARD_PRECISION
+        * u128::from(multiplier_bp)
+        / 10000) as u64
+}
+
+pub fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    let expected = anchor_lang::error::ERROR_CODE_OFFSET + error as u32;
+    match result {
+        Err(err) => match err.unwrap() {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+                assert_eq!(code, expected, "unexpected program error")
+            }
+            other => panic!("unexpected transaction error: {other:?}"),
+        },
+        Ok(()) => panic!("transaction succeeded, expected error {expected}"),
+    }
+}
+
+/// Transfer fee applied to a Token-2022 mint created by the harness.
+#[derive(Clone, Copy)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    pub fn calculate(&self, amount: u64) -> u64 {
+        let fee = (u128::from(amount) * u128::from(self.basis_points)).div_ceil(10000) as u64;
+        fee.min(self.maximum_fee)
+    }
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub admin: Keypair,
+    pub token_program: Pubkey,
+}
+
+impl TestEnv {
+    /// Starts a bank with the program loaded and a `Config` whose admin is `admin`.
+    /// `init_config` itself only accepts the hard-coded deployer key, so the
+    /// account is written directly.
+    pub async fn new(token_program: Pubkey) -> Self {
+        let mut program_test = ProgramTest::new(
+            "stake_pool",
+            stake_pool::ID,
+            processor!(process_instruction),
+        );
+
+        let admin = Keypair::new();
+        program_test.add_account(
+            admin.pubkey(),
+            Account::new(100_000_000_000, 0, &system_program::ID),
+        );
+
+        let (config, bump) =
+            Pubkey::find_program_address(&[CONFIG_PREFIX.as_bytes()], &stake_pool::ID);
+        let mut data = Vec::new();
+        Config {
+            bump,
+            admin: admin.pubkey(),
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        program_test.add_account(
+            config,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: stake_pool::ID,
+                ..Account::default()
+            },
+        );
+
+        let context = program_test.start_with_context().await;
+        Self {
+            context,
+            admin,
+            token_program,
+        }
+    }
+
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let payer = self.context.payer.insecure_clone();
+        let mut all_signers = vec![&payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    /// Moves the bank to a new slot and the clock `seconds` forward.
+    pub async fn warp(&mut self, seconds: i64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        self.context.warp_to_slot(clock.slot + 1).unwrap();
+        clock.slot += 1;
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn create_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let payer = self.context.payer.pubkey();
+        self.send(
+            &[system_instruction::transfer(
+                &payer,
+                &user.pubkey(),
+                10_000_000_000,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+        user
+    }
+
+    pub async fn create_mint(&mut self, transfer_fee: Option<TransferFee>) -> Pubkey {
+        let mint = Keypair::new();
+        let authority = self.admin.pubkey();
+        let space = match transfer_fee {
+            Some(_) => ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap(),
+            None => spl_token_2022::state::Mint::LEN,
+        };
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+
+        let mut instructions = vec![system_instruction::create_account(
+            &self.context.payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &self.token_program,
+        )];
+        if let Some(fee) = transfer_fee {
+            instructions.push(
+                initialize_transfer_fee_config(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    Some(&authority),
+                    Some(&authority),
+                    fee.basis_points,
+                    fee.maximum_fee,
+                )
+                .unwrap(),
+            );
+        }
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &authority,
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        );
+        self.send(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Creates an empty, non-associated token account owned by `owner`.
+    pub async fn create_token_account(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let space = spl_token_2022::state::Account::LEN;
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.context.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &self.token_program,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &self.token_program,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        self.send(&instructions, &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    pub fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+
+    /// Creates `owner`'s associated token account and mints `amount` into it.
+    pub async fn fund(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let account = self.ata(owner, mint);
+        let mut instructions = Vec::new();
+        if self
+            .context
+            .banks_client
+            .get_account(account)
+            .await
+            .unwrap()
+            .is_none()
+        {
+            instructions.push(create_associated_token_account(
+                &self.context.payer.pubkey(),
+                owner,
+                mint,
+                &self.token_program,
+            ));
+        }
+        if amount > 0 {
+            instructions.push(
+                spl_token_2022::instruction::mint_to(
+                    &self.token_program,
+                    mint,
+                    &account,
+                    &self.admin.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        let admin = self.admin.insecure_clone();
+        let signers: &[&Keypair] = if amount > 0 { &[&admin] } else { &[] };
+        self.send(&instructions, signers).await.unwrap();
+        account
+    }
+
+    pub async fn balance(&mut self, account: &Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*account)
+            .await
+            .unwrap()
+            .unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    /// Lamports held by `address`, 0 once the account is closed.
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap();
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn stake_pool(&mut self, pool: &TestPool) -> StakePool {
+        self.account(&pool.address).await
+    }
+
+    pub async fn stake_entry(&mut self, entry: &Pubkey) -> StakeEntry {
+        self.account(entry).await
+    }
+}
+
+/// A pool created through `init_pool` by the config admin.
+pub struct TestPool {
+    pub identifier: String,
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub receipt_mint: Option<Pubkey>,
+}
+
+impl TestPool {
+    pub fn pool_token_account(&self, env: &TestEnv) -> Pubkey {
+        env.ata(&self.address, &self.mint)
+    }
+
+    pub fn reward_vault(&self, env: &TestEnv) -> Pubkey {
+        env.ata(&self.address, &self.reward_mint)
+    }
+
+    pub fn entry(&self, staker: &Pubkey) -> Pubkey {
+        entry_address(&self.identifier, &self.address, staker)
+    }
+
+    pub fn receipt_account(&self, env: &TestEnv, owner: &Pubkey) -> Option<Pubkey> {
+        self.receipt_mint
+            .map(|receipt_mint| env.ata(owner, &receipt_mint))
+    }
+}
+
+impl TestEnv {
+    pub fn init_pool_ix(
+        &self,
+        payer: &Pubkey,
+        identifier: &str,
+        mint: &Pubkey,
+        reward_mint: &Pubkey,
+        ix: InitPoolIx,
+    ) -> Instruction {
+        let stake_pool = pool_address(identifier);
+        Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::InitPoolCtx {
+                stake_pool,
+                pool_token_account: self.ata(&stake_pool, mint),
+                mint: *mint,
+                reward_vault: self.ata(&stake_pool, reward_mint),
+                reward_mint: *reward_mint,
+                payer_token_account: self.ata(payer, reward_mint),
+                config: config_address(),
+                payer: *payer,
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::InitPool { ix }.data(),
+        }
+    }
+
+    /// Creates a pool staking and paying rewards in `mint`, funded with `deposite_amount`.
+    pub async fn create_pool(
+        &mut self,
+        mint: &Pubkey,
+        min_stake_seconds: u32,
+        stake_reward: u64,
+        deposite_amount: u64,
+    ) -> TestPool {
+        let identifier = "pool".to_string();
+        let admin = self.admin.insecure_clone();
+        self.fund(&admin.pubkey(), mint, deposite_amount * 2).await;
+        let ix = self.init_pool_ix(
+            &admin.pubkey(),
+            &identifier,
+            mint,
+            mint,
+            InitPoolIx {
+                min_stake_seconds,
+                stake_reward,
+                deposite_amount,
+                identifier: identifier.clone(),
+            },
+        );
+        self.send(&[ix], &[&admin]).await.unwrap();
+        TestPool {
+            address: pool_address(&identifier),
+            identifier,
+            mint: *mint,
+            reward_mint: *mint,
+            receipt_mint: None,
+        }
+    }
+
+    /// Enables receipts on `pool`, stakes and unstakes through the helpers then
+    /// mint and burn them from the staker's associated receipt account.
+    pub async fn init_receipt_mint(&mut self, pool: &mut TestPool) {
+        let receipt_mint = Pubkey::find_program_address(
+            &[RECEIPT_MINT_PREFIX.as_bytes(), pool.address.as_ref()],
+            &stake_pool::ID,
+        )
+        .0;
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::InitReceiptMintCtx {
+                stake_pool: pool.address,
+                receipt_mint,
+                stake_mint: pool.mint,
+                payer: admin.pubkey(),
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::InitReceiptMint {}.data(),
+        };
+        self.send(&[ix], &[&admin]).await.unwrap();
+        pool.receipt_mint = Some(receipt_mint);
+    }
+
+    /// Moves `amount` of `mint` between the associated accounts of `from` and `to`.
+    pub async fn transfer(&mut self, from: &Keypair, to: &Pubkey, mint: &Pubkey, amount: u64) {
+        let destination = self.fund(to, mint, 0).await;
+        let ix = spl_token_2022::instruction::transfer_checked(
+            &self.token_program,
+            &self.ata(&from.pubkey(), mint),
+            mint,
+            &destination,
+            &from.pubkey(),
+            &[],
+            amount,
+            DECIMALS,
+        )
+        .unwrap();
+        self.send(&[ix], &[from]).await.unwrap();
+    }
+
+    pub async fn init_entry(&mut self, pool: &TestPool, user: &Keypair) -> Pubkey {
+        let stake_entry = pool.entry(&user.pubkey());
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::InitEntryCtx {
+                stake_entry,
+                stake_pool: pool.address,
+                payer: user.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::InitEntry {
+                identifier: pool.identifier.clone(),
+            }
+            .data(),
+        };
+        self.send(&[ix], &[user]).await.unwrap();
+        stake_entry
+    }
+
+    pub async fn stake(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        amount: u64,
+        lock_tier: Option<u8>,
+    ) -> Result<(), BanksClientError> {
+        let stake_entry = pool.entry(&user.pubkey());
+        self.stake_into(pool, user, &stake_entry, amount, lock_tier)
+            .await
+    }
+
+    pub async fn stake_into(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        stake_entry: &Pubkey,
+        amount: u64,
+        lock_tier: Option<u8>,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::StakeTokenCtx {
+                stake_pool: pool.address,
+                pool_token_account: pool.pool_token_account(self),
+                stake_entry: *stake_entry,
+                staker_registry: self.registry(pool, &user.pubkey()),
+                stake_mint: pool.mint,
+                user: user.pubkey(),
+                user_token_account: self.ata(&user.pubkey(), &pool.mint),
+                receipt_mint: pool.receipt_mint,
+                user_receipt_account: pool.receipt_account(self, &user.pubkey()),
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::StakeTokens { amount, lock_tier }.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    /// Opens grant `grant_id` from `funder` to `beneficiary` and returns its entry.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn stake_for(
+        &mut self,
+        pool: &TestPool,
+        funder: &Keypair,
+        beneficiary: &Pubkey,
+        grant_id: u64,
+        amount: u64,
+        lock_tier: Option<u8>,
+        cliff_seconds: u32,
+        vesting_seconds: u32,
+    ) -> Result<Pubkey, BanksClientError> {
+        let stake_entry =
+            StakeEntry::grant_address(&pool.address, beneficiary, &funder.pubkey(), grant_id);
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::StakeForCtx {
+                stake_pool: pool.address,
+                pool_token_account: pool.pool_token_account(self),
+                stake_entry,
+                staker_registry: self.registry(pool, beneficiary),
+                stake_mint: pool.mint,
+                funder: funder.pubkey(),
+                funder_token_account: self.ata(&funder.pubkey(), &pool.mint),
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::StakeFor {
+                beneficiary: *beneficiary,
+                grant_id,
+                amount,
+                lock_tier,
+                cliff_seconds,
+                vesting_seconds,
+            }
+            .data(),
+        };
+        self.send(&[ix], &[funder]).await.map(|_| stake_entry)
+    }
+
+    fn unstake_accounts(
+        &self,
+        pool: &TestPool,
+        user: &Pubkey,
+        stake_entry: &Pubkey,
+    ) -> Vec<AccountMeta> {
+        stake_pool::accounts::UnstakeTokenCtx {
+            stake_pool: pool.address,
+            pool_token_account: pool.pool_token_account(self),
+            stake_entry: *stake_entry,
+            staker_registry: self.registry(pool, user),
+            stake_mint: pool.mint,
+            reward_vault: pool.reward_vault(self),
+            reward_mint: pool.reward_mint,
+            user: *user,
+            user_token_account: self.ata(user, &pool.mint),
+            user_reward_account: self.ata(user, &pool.reward_mint),
+            receipt_mint: pool.receipt_mint,
+            user_receipt_account: pool.receipt_account(self, user),
+            token_program: self.token_program,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    pub async fn unstake(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        close_entry: bool,
+    ) -> Result<(), BanksClientError> {
+        let stake_entry = pool.entry(&user.pubkey());
+        self.unstake_from(pool, user, &stake_entry, close_entry)
+            .await
+    }
+
+    pub async fn unstake_from(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        stake_entry: &Pubkey,
+        close_entry: bool,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: self.unstake_accounts(pool, &user.pubkey(), stake_entry),
+            data: stake_pool::instruction::UnstakeTokens { close_entry }.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn unstake_partial(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let stake_entry = pool.entry(&user.pubkey());
+        self.unstake_partial_from(pool, user, &stake_entry, amount)
+            .await
+    }
+
+    pub async fn unstake_partial_from(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        stake_entry: &Pubkey,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: self.unstake_accounts(pool, &user.pubkey(), stake_entry),
+            data: stake_pool::instruction::UnstakePartial { amount }.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn compound(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::CompoundCtx {
+                stake_pool: pool.address,
+                stake_entry: pool.entry(&user.pubkey()),
+                staker_registry: self.registry(pool, &user.pubkey()),
+                user: user.pubkey(),
+                receipt_mint: pool.receipt_mint,
+                user_receipt_account: pool.receipt_account(self, &user.pubkey()),
+                token_program: pool.receipt_mint.map(|_| self.token_program),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::Compound {}.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn emergency_unstake(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        stake_entry: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::EmergencyUnstakeCtx {
+                stake_pool: pool.address,
+                pool_token_account: pool.pool_token_account(self),
+                stake_entry: *stake_entry,
+                staker_registry: self.registry(pool, &user.pubkey()),
+                stake_mint: pool.mint,
+                user: user.pubkey(),
+                user_token_account: self.ata(&user.pubkey(), &pool.mint),
+                receipt_mint: None,
+                user_receipt_account: None,
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::EmergencyUnstake {}.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn early_unstake(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        treasury_token_account: Option<Pubkey>,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::EarlyUnstakeTokenCtx {
+                stake_pool: pool.address,
+                pool_token_account: pool.pool_token_account(self),
+                stake_entry: pool.entry(&user.pubkey()),
+                staker_registry: self.registry(pool, &user.pubkey()),
+                stake_mint: pool.mint,
+                user: user.pubkey(),
+                user_token_account: self.ata(&user.pubkey(), &pool.mint),
+                receipt_mint: None,
+                user_receipt_account: None,
+                treasury_token_account,
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::EarlyUnstakeTokens {}.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn complete_withdrawal(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::CompleteWithdrawalCtx {
+                stake_pool: pool.address,
+                pool_token_account: pool.pool_token_account(self),
+                stake_entry: pool.entry(&user.pubkey()),
+                stake_mint: pool.mint,
+                user: user.pubkey(),
+                user_token_account: self.ata(&user.pubkey(), &pool.mint),
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::CompleteWithdrawal {}.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn close_entry(
+        &mut self,
+        user: &Keypair,
+        stake_entry: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::CloseEntryCtx {
+                stake_entry: *stake_entry,
+                user: user.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::CloseEntry {}.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn claim(&mut self, pool: &TestPool, user: &Keypair) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::ClaimRewardsCtx {
+                stake_pool: pool.address,
+                reward_vault: pool.reward_vault(self),
+                stake_entry: pool.entry(&user.pubkey()),
+                staker_registry: self.registry(pool, &user.pubkey()),
+                reward_mint: pool.reward_mint,
+                user: user.pubkey(),
+                user_reward_account: self.ata(&user.pubkey(), &pool.reward_mint),
+                user_receipt_account: pool.receipt_account(self, &user.pubkey()),
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::ClaimRewards {}.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn withdraw(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::WithdrawTokensCtx {
+                stake_pool: pool.address,
+                reward_vault: pool.reward_vault(self),
+                reward_mint: pool.reward_mint,
+                user: user.pubkey(),
+                user_token_account: self.ata(&user.pubkey(), &pool.reward_mint),
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::WithdrawTokens { amount }.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    pub async fn deposite(
+        &mut self,
+        pool: &TestPool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::DepositeTokensCtx {
+                stake_pool: pool.address,
+                reward_vault: pool.reward_vault(self),
+                reward_mint: pool.reward_mint,
+                user: user.pubkey(),
+                user_token_account: self.ata(&user.pubkey(), &pool.reward_mint),
+                token_program: self.token_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::DepositeTokens { amount }.data(),
+        };
+        self.send(&[ix], &[user]).await
+    }
+
+    /// Sends a pool update signed by `payer`, e.g. `stake_pool::instruction::UpdatePoolReward`.
+    pub async fn update_pool<T: InstructionData>(
+        &mut self,
+        pool: &TestPool,
+        payer: &Keypair,
+        data: T,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::UpdatePoolCtx {
+                stake_pool: pool.address,
+                payer: payer.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        };
+        self.send(&[ix], &[payer]).await
+    }
+
+    /// Sends an authority-only pool update, e.g. `stake_pool::instruction::UpdatePoolLimits`.
+    pub async fn update_pool_as_authority<T: InstructionData>(
+        &mut self,
+        pool: &TestPool,
+        payer: &Keypair,
+        data: T,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::UpdatePoolAuthorityCtx {
+                stake_pool: pool.address,
+                payer: payer.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        };
+        self.send(&[ix], &[payer]).await
+    }
+
+    /// Proposes `new_authority` and accepts the transfer as `new_authority`.
+    pub async fn transfer_pool_authority(
+        &mut self,
+        pool: &TestPool,
+        authority: &Keypair,
+        new_authority: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let propose = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::PoolAuthorityCtx {
+                stake_pool: pool.address,
+                authority: authority.pubkey(),
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::ProposePoolAuthority {
+                new_authority: Some(new_authority.pubkey()),
+            }
+            .data(),
+        };
+        let accept = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::AcceptPoolAuthorityCtx {
+                stake_pool: pool.address,
+                new_authority: new_authority.pubkey(),
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::AcceptPoolAuthority {}.data(),
+        };
+        self.send(&[propose, accept], &[authority, new_authority])
+            .await
+    }
+
+    pub async fn set_pool_operator(
+        &mut self,
+        pool: &TestPool,
+        authority: &Keypair,
+        operator: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::PoolAuthorityCtx {
+                stake_pool: pool.address,
+                authority: authority.pubkey(),
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::SetPoolOperator {
+                operator: *operator,
+            }
+            .data(),
+        };
+        self.send(&[ix], &[authority]).await
+    }
+
+    /// Sends a timelocked parameter instruction signed by the pool `authority`,
+    /// e.g. `stake_pool::instruction::ProposePoolParams`.
+    pub async fn pool_params<T: InstructionData>(
+        &mut self,
+        pool: &TestPool,
+        authority: &Keypair,
+        data: T,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::PoolParamsCtx {
+                stake_pool: pool.address,
+                authority: authority.pubkey(),
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        };
+        self.send(&[ix], &[authority]).await
+    }
+
+    pub async fn apply_pool_params(&mut self, pool: &TestPool) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::ApplyPoolParamsCtx {
+                stake_pool: pool.address,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::ApplyPoolParams {}.data(),
+        };
+        self.send(&[ix], &[]).await
+    }
+
+    pub async fn migrate_pool(
+        &mut self,
+        pool: &TestPool,
+        payer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let pool_token_account = pool.pool_token_account(self);
+        self.migrate_pool_with(pool, payer, &pool_token_account)
+            .await
+    }
+
+    pub async fn migrate_pool_with(
+        &mut self,
+        pool: &TestPool,
+        payer: &Keypair,
+        pool_token_account: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::MigratePoolCtx {
+                stake_pool: pool.address,
+                pool_token_account: *pool_token_account,
+                stake_mint: pool.mint,
+                payer: payer.pubkey(),
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::MigratePool {}.data(),
+        };
+        self.send(&[ix], &[payer]).await
+    }
+
+    pub async fn migrate_entry(
+        &mut self,
+        pool: &TestPool,
+        staker: &Pubkey,
+        payer: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::MigrateEntryCtx {
+                stake_entry: pool.entry(staker),
+                stake_pool: pool.address,
+                payer: payer.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::MigrateEntry {}.data(),
+        };
+        self.send(&[ix], &[payer]).await
+    }
+
+    /// Writes a program owned, rent exempt account holding `data` padded to `size`.
+    pub async fn set_program_account(&mut self, address: &Pubkey, mut data: Vec<u8>, size: usize) {
+        data.resize(size, 0);
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let account = Account {
+            lamports: rent.minimum_balance(size),
+            data,
+            owner: stake_pool::ID,
+            ..Account::default()
+        };
+        self.context.set_account(address, &account.into());
+    }
+
+    pub fn registry(&self, pool: &TestPool, staker: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                STAKER_REGISTRY_PREFIX.as_bytes(),
+                pool.address.as_ref(),
+                staker.as_ref(),
+            ],
+            &stake_pool::ID,
+        )
+        .0
+    }
+
+    /// Opens the next numbered position of `user` and returns its entry address.
+    pub async fn open_position(&mut self, pool: &TestPool, user: &Keypair) -> Pubkey {
+        let staker_registry = self.registry(pool, &user.pubkey());
+        let position = match self
+            .context
+            .banks_client
+            .get_account(staker_registry)
+            .await
+            .unwrap()
+        {
+            Some(_) => {
+                self.account::<StakerRegistry>(&staker_registry)
+                    .await
+                    .position_count
+            }
+            None => 0,
+        };
+        let stake_entry = StakerRegistry::position_address(&pool.address, &user.pubkey(), position);
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts: stake_pool::accounts::OpenPositionCtx {
+                staker_registry,
+                stake_entry,
+                stake_pool: pool.address,
+                payer: user.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: stake_pool::instruction::OpenPosition {}.data(),
+        };
+        self.send(&[ix], &[user]).await.unwrap();
+        stake_entry
+    }
+
+    /// Simulates `aggregate_positions` over `positions` and decodes its return data.
+    pub async fn aggregate_positions(
+        &mut self,
+        pool: &TestPool,
+        staker: &Pubkey,
+        start: u32,
+        positions: &[Pubkey],
+    ) -> Result<PositionsSummary, BanksClientError> {
+        let mut accounts = stake_pool::accounts::AggregatePositionsCtx {
+            staker_registry: self.registry(pool, staker),
+            stake_pool: pool.address,
+        }
+        .to_account_metas(None);
+        accounts.extend(
+            positions
+                .iter()
+                .map(|position| AccountMeta::new_readonly(*position, false)),
+        );
+        let ix = Instruction {
+            program_id: stake_pool::ID,
+            accounts,
+            data: stake_pool::instruction::AggregatePositions {
+                start,
+                len: positions.len() as u32,
+            }
+            .data(),
+        };
+        self.simulate(ix).await
+    }
+
+    /// Simulates `ix` and decodes the instruction's return data as `T`.
+    pub async fn simulate<T: AnchorDeserialize>(
+        &mut self,
+        ix: Instruction,
+    ) -> Result<T, BanksClientError> {
+        let payer = self.context.payer.insecure_clone();
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let transaction =
+            Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await?;
+        if let Err(err) = simulation.result.unwrap() {
+            return Err(BanksClientError::TransactionError(err));
+        }
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        Ok(T::deserialize(&mut return_data.data.as_slice()).unwrap())
+    }
+}
+
+/// A fresh environment on `token_program` with a pool staking and paying rewards in
+/// a new mint at `STAKE_REWARD`, funded with `REWARD_DEPOSIT` and using `lock_tiers`.
+pub async fn setup_pool(
+    token_program: Pubkey,
+    min_stake_seconds: u32,
+    lock_tiers: Vec<LockTier>,
+) -> (TestEnv, TestPool) {
+    let mut env = TestEnv::new(token_program).await;
+    let mint = env.create_mint(None).await;
+    let pool = env
+        .create_pool(&mint, min_stake_seconds, STAKE_REWARD, REWARD_DEPOSIT)
+        .await;
+    if !lock_tiers.is_empty() {
+        let admin = env.admin.insecure_clone();
+        env.update_pool(
+            &pool,
+            &admin,
+            stake_pool::instruction::UpdatePoolLockTiers { lock_tiers },
+        )
+        .await
+        .unwrap();
+    }
+    (env, pool)
+}
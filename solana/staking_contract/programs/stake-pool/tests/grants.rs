@@ -0,0 +1,180 @@
+mod common;
+
+use common::*;
+use solana_sdk::signature::Signer;
+use stake_pool::errors::ErrorCode;
+use stake_pool::StakeEntry;
+
+const GRANT_AMOUNT: u64 = 10_000_000;
+const CLIFF_SECONDS: u32 = 100_000;
+const VESTING_SECONDS: u32 = 400_000;
+
+#[tokio::test]
+async fn granted_stake_vests_to_beneficiary() {
+    let (mut env, pool) = setup_pool(anchor_spl::token::ID, 0, Vec::new()).await;
+    let funder = env.create_user().await;
+    let beneficiary = env.create_user().await;
+    env.fund(&funder.pubkey(), &pool.mint, GRANT_AMOUNT).await;
+    let beneficiary_token_account = env.fund(&beneficiary.pubkey(), &pool.mint, 0).await;
+
+    assert_error(
+        env.stake_for(
+            &pool,
+            &funder,
+            &beneficiary.pubkey(),
+            0,
+            GRANT_AMOUNT,
+            None,
+            2,
+            1,
+        )
+        .await
+        .map(|_| ()),
+        ErrorCode::InvalidVestingSchedule,
+    );
+    let stake_entry = env
+        .stake_for(
+            &pool,
+            &funder,
+            &beneficiary.pubkey(),
+            0,
+            GRANT_AMOUNT,
+            None,
+            CLIFF_SECONDS,
+            VESTING_SECONDS,
+        )
+        .await
+        .unwrap();
+    let entry: StakeEntry = env.account(&stake_entry).await;
+    assert_eq!(entry.staker, beneficiary.pubkey());
+    assert_eq!(entry.amount, GRANT_AMOUNT);
+    let grant = entry.grant.unwrap();
+    assert_eq!(grant.funder, funder.pubkey());
+    assert_eq!(grant.amount, GRANT_AMOUNT);
+
+    // only the beneficiary can unstake, and nothing before the cliff
+    assert_error(
+        env.unstake_from(&pool, &funder, &stake_entry, false).await,
+        ErrorCode::InvalidStakeEntryOwner,
+    );
+    assert_error(
+        env.unstake_partial_from(&pool, &beneficiary, &stake_entry, 1)
+            .await,
+        ErrorCode::VestingLocked,
+    );
+
+    // half way through the schedule half of the grant is vested
+    env.warp(i64::from(VESTING_SECONDS) / 2).await;
+    assert_error(
+        env.unstake_partial_from(&pool, &beneficiary, &stake_entry, GRANT_AMOUNT / 2 + 1)
+            .await,
+        ErrorCode::VestingLocked,
+    );
+    env.unstake_partial_from(&pool, &beneficiary, &stake_entry, GRANT_AMOUNT / 2)
+        .await
+        .unwrap();
+    // rewards are paid in the stake mint as well
+    assert_eq!(
+        env.balance(&beneficiary_token_account).await,
+        GRANT_AMOUNT / 2
+            + expected_rewards(
+                GRANT_AMOUNT,
+                STAKE_REWARD,
+                i64::from(VESTING_SECONDS) / 2,
+                10000
+            )
+    );
+
+    env.warp(i64::from(VESTING_SECONDS) / 2).await;
+    env.unstake_from(&pool, &beneficiary, &stake_entry, false)
+        .await
+        .unwrap();
+    assert!(env.balance(&beneficiary_token_account).await > GRANT_AMOUNT);
+    let entry: StakeEntry = env.account(&stake_entry).await;
+    assert_eq!(entry.amount, 0);
+    assert!(entry.grant.is_none());
+}
+
+#[tokio::test]
+async fn grants_open_their_own_entries() {
+    let (mut env, pool) = setup_pool(anchor_spl::token::ID, 0, Vec::new()).await;
+    let funder = env.create_user().await;
+    let beneficiary = env.create_user().await;
+    env.fund(&funder.pubkey(), &pool.mint, GRANT_AMOUNT * 2)
+        .await;
+    let beneficiary_token_account = env
+        .fund(&beneficiary.pubkey(), &pool.mint, GRANT_AMOUNT)
+        .await;
+    let own_entry = env.init_entry(&pool, &beneficiary).await;
+    env.stake(&pool, &beneficiary, GRANT_AMOUNT, None)
+        .await
+        .unwrap();
+    let before: StakeEntry = env.account(&own_entry).await;
+
+    env.warp(1).await;
+    let first = env
+        .stake_for(
+            &pool,
+            &funder,
+            &beneficiary.pubkey(),
+            0,
+            GRANT_AMOUNT,
+            None,
+            0,
+            VESTING_SECONDS,
+        )
+        .await
+        .unwrap();
+    // a grant id can only be used once
+    assert!(env
+        .stake_for(&pool, &funder, &beneficiary.pubkey(), 0, 1, None, 0, 1)
+        .await
+        .is_err());
+    let second = env
+        .stake_for(
+            &pool,
+            &funder,
+            &beneficiary.pubkey(),
+            1,
+            GRANT_AMOUNT,
+            None,
+            0,
+            0,
+        )
+        .await
+        .unwrap();
+    assert_ne!(first, second);
+
+    // the beneficiary's own entry keeps its lock start and has no grant
+    let after: StakeEntry = env.account(&own_entry).await;
+    assert_eq!(after.last_staked_at, before.last_staked_at);
+    assert_eq!(after.amount, GRANT_AMOUNT);
+    assert!(after.grant.is_none());
+    assert_eq!(env.stake_pool(&pool).await.total_stakers, 3);
+
+    // an emergency exit releases the vested part only
+    env.warp(i64::from(VESTING_SECONDS) / 4).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolPaused { paused: true },
+    )
+    .await
+    .unwrap();
+    let balance = env.balance(&beneficiary_token_account).await;
+    env.emergency_unstake(&pool, &beneficiary, &first)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.balance(&beneficiary_token_account).await - balance,
+        GRANT_AMOUNT / 4
+    );
+    let entry: StakeEntry = env.account(&first).await;
+    assert_eq!(entry.amount, GRANT_AMOUNT - GRANT_AMOUNT / 4);
+    assert!(entry.grant.is_some());
+    assert_error(
+        env.emergency_unstake(&pool, &beneficiary, &first).await,
+        ErrorCode::VestingLocked,
+    );
+}
@@ -0,0 +1,267 @@
+mod common;
+
+use anchor_lang::{AnchorSerialize, Discriminator};
+use common::*;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use stake_pool::errors::ErrorCode;
+use stake_pool::{
+    StakeEntry, StakeEntryV0, StakePool, StakePoolV0, StakePoolV1, BASE_MULTIPLIER_BP,
+    STAKE_ENTRY_SIZE, STAKE_ENTRY_VERSION, STAKE_POOL_DEFAULT_SIZE, STAKE_POOL_PREFIX,
+    STAKE_POOL_VERSION,
+};
+
+const REWARD_RESERVE: u64 = 1_000_000_000;
+// account sizes pools and entries were created with before versioning
+const POOL_V0_SIZE: usize = 130;
+const ENTRY_V0_SIZE: usize = 128;
+
+/// Writes a pool and one entry in the pre-versioning layout, with the vault
+/// holding the staked amount plus the reward reserve.
+async fn setup_v0(env: &mut TestEnv, entry_identifier: &str) -> (TestPool, Keypair, i64) {
+    let mint = env.create_mint(None).await;
+    let identifier = "legacy".to_string();
+    let (address, bump) = Pubkey::find_program_address(
+        &[STAKE_POOL_PREFIX.as_bytes(), identifier.as_bytes()],
+        &stake_pool::ID,
+    );
+    let pool = TestPool {
+        identifier: identifier.clone(),
+        address,
+        mint,
+        reward_mint: mint,
+        receipt_mint: None,
+    };
+
+    let mut data = StakePool::discriminator().to_vec();
+    StakePoolV0 {
+        bump,
+        authority: env.admin.pubkey(),
+        total_staked: STAKE_AMOUNT,
+        total_stakers: 1,
+        min_stake_seconds: Some(3600),
+        stake_reward: STAKE_REWARD,
+        mint,
+        identifier: identifier.clone(),
+    }
+    .serialize(&mut data)
+    .unwrap();
+    env.set_program_account(&address, data, POOL_V0_SIZE).await;
+    env.fund(&address, &mint, STAKE_AMOUNT + REWARD_RESERVE)
+        .await;
+
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &mint, 0).await;
+    let last_staked_at = env.now().await - SECONDS_IN_YEAR / 2;
+    let mut data = StakeEntry::discriminator().to_vec();
+    StakeEntryV0 {
+        bump: 255,
+        staker: user.pubkey(),
+        stake_pool: address,
+        amount: STAKE_AMOUNT,
+        last_staked_at,
+        identifier: entry_identifier.to_string(),
+    }
+    .serialize(&mut data)
+    .unwrap();
+    env.set_program_account(&pool.entry(&user.pubkey()), data, ENTRY_V0_SIZE)
+        .await;
+
+    (pool, user, last_staked_at)
+}
+
+#[tokio::test]
+async fn migrate_pool_and_entry_to_v1() {
+    let mut env = TestEnv::new(anchor_spl::token::ID).await;
+    let (pool, user, last_staked_at) = setup_v0(&mut env, "legacy").await;
+    let payer = env.create_user().await;
+
+    // entries can only be migrated once their pool is
+    assert!(env
+        .migrate_entry(&pool, &user.pubkey(), &payer)
+        .await
+        .is_err());
+
+    // the reward reserve is read from the vault, a decoy account owned by the pool is rejected
+    let decoy = env.create_token_account(&pool.address, &pool.mint).await;
+    assert!(env.migrate_pool_with(&pool, &payer, &decoy).await.is_err());
+
+    env.migrate_pool(&pool, &payer).await.unwrap();
+    let account = env
+        .context
+        .banks_client
+        .get_account(pool.address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), STAKE_POOL_DEFAULT_SIZE);
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    assert_eq!(
+        account.lamports,
+        rent.minimum_balance(STAKE_POOL_DEFAULT_SIZE)
+    );
+
+    let stake_pool = env.stake_pool(&pool).await;
+    assert_eq!(stake_pool.version, STAKE_POOL_VERSION);
+    assert_eq!(stake_pool.authority, env.admin.pubkey());
+    assert_eq!(stake_pool.operator, env.admin.pubkey());
+    assert_eq!(stake_pool.reward_mint, pool.mint);
+    assert_eq!(stake_pool.total_staked, STAKE_AMOUNT);
+    assert_eq!(stake_pool.total_stakers, 1);
+    assert_eq!(stake_pool.reward_reserve, REWARD_RESERVE);
+    assert_eq!(
+        stake_pool.total_boosted_staked,
+        u128::from(STAKE_AMOUNT) * u128::from(BASE_MULTIPLIER_BP)
+    );
+
+    env.migrate_entry(&pool, &user.pubkey(), &payer)
+        .await
+        .unwrap();
+    let entry_address = pool.entry(&user.pubkey());
+    let account = env
+        .context
+        .banks_client
+        .get_account(entry_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), STAKE_ENTRY_SIZE);
+
+    let stake_entry = env.stake_entry(&entry_address).await;
+    assert_eq!(stake_entry.version, STAKE_ENTRY_VERSION);
+    assert_eq!(stake_entry.staker, user.pubkey());
+    assert_eq!(stake_entry.amount, STAKE_AMOUNT);
+    assert_eq!(stake_entry.last_staked_at, last_staked_at);
+    assert_eq!(stake_entry.multiplier_bp, BASE_MULTIPLIER_BP);
+    // rewards earned under the V0 formula are carried over
+    assert_eq!(stake_entry.pending_rewards, STAKE_AMOUNT / 20);
+
+    // migrating again leaves the accounts untouched
+    env.warp(SECONDS_IN_YEAR / 2).await;
+    env.migrate_pool(&pool, &payer).await.unwrap();
+    env.migrate_entry(&pool, &user.pubkey(), &payer)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.stake_entry(&entry_address).await.pending_rewards,
+        STAKE_AMOUNT / 20
+    );
+
+    env.unstake(&pool, &user, false).await.unwrap();
+    assert_eq!(
+        env.balance(&env.ata(&user.pubkey(), &pool.mint)).await,
+        STAKE_AMOUNT
+            + STAKE_AMOUNT / 20
+            + expected_rewards(
+                STAKE_AMOUNT,
+                STAKE_REWARD,
+                SECONDS_IN_YEAR / 2,
+                BASE_MULTIPLIER_BP
+            )
+    );
+}
+
+#[tokio::test]
+async fn v0_accounts_must_be_migrated_before_use() {
+    let mut env = TestEnv::new(anchor_spl::token::ID).await;
+    let (pool, user, _) = setup_v0(&mut env, "legacy").await;
+
+    assert!(env.unstake(&pool, &user, false).await.is_err());
+    let payer = env.create_user().await;
+    env.migrate_pool(&pool, &payer).await.unwrap();
+    assert!(env.unstake(&pool, &user, false).await.is_err());
+    env.migrate_entry(&pool, &user.pubkey(), &payer)
+        .await
+        .unwrap();
+    env.unstake(&pool, &user, false).await.unwrap();
+}
+
+#[tokio::test]
+async fn migrate_entry_with_seed_length_identifier() {
+    let mut env = TestEnv::new(anchor_spl::token::ID).await;
+    // `init_entry` accepted any identifier that fits in a seed
+    let identifier = "x".repeat(32);
+    let (pool, user, _) = setup_v0(&mut env, &identifier).await;
+    let payer = env.create_user().await;
+
+    env.migrate_pool(&pool, &payer).await.unwrap();
+    env.migrate_entry(&pool, &user.pubkey(), &payer)
+        .await
+        .unwrap();
+    let stake_entry = env.stake_entry(&pool.entry(&user.pubkey())).await;
+    assert_eq!(stake_entry.identifier, identifier);
+    assert_eq!(stake_entry.amount, STAKE_AMOUNT);
+}
+
+/// Rewrites a live pool in the V1 layout, keeping the account at its current size.
+async fn write_v1_pool(env: &mut TestEnv, pool: &TestPool) {
+    let stake_pool = env.stake_pool(pool).await;
+    let mut data = StakePool::discriminator().to_vec();
+    StakePoolV1 {
+        version: 1,
+        bump: stake_pool.bump,
+        authority: stake_pool.authority,
+        total_staked: stake_pool.total_staked,
+        total_stakers: stake_pool.total_stakers,
+        min_stake_seconds: stake_pool.min_stake_seconds,
+        stake_reward: stake_pool.stake_reward,
+        mint: stake_pool.mint,
+        identifier: stake_pool.identifier,
+        reward_per_token_stored: stake_pool.reward_per_token_stored,
+        last_update_time: stake_pool.last_update_time,
+        reward_reserve: stake_pool.reward_reserve,
+        lock_tiers: stake_pool.lock_tiers,
+        reward_mint: stake_pool.reward_mint,
+        early_unstake_penalty_bp: stake_pool.early_unstake_penalty_bp,
+        penalty_treasury: stake_pool.penalty_treasury,
+        pending_authority: stake_pool.pending_authority,
+        operator: stake_pool.operator,
+        paused: stake_pool.paused,
+        rewards_start: stake_pool.rewards_start,
+        rewards_end: stake_pool.rewards_end,
+        total_reward_budget: stake_pool.total_reward_budget,
+        remaining_reward_budget: stake_pool.remaining_reward_budget,
+        total_boosted_staked: stake_pool.total_boosted_staked,
+        unbonding_seconds: stake_pool.unbonding_seconds,
+        total_unbonding: stake_pool.total_unbonding,
+        compound_enabled: stake_pool.compound_enabled,
+        receipt_mint: stake_pool.receipt_mint,
+        max_total_staked: stake_pool.max_total_staked,
+        min_stake_amount: stake_pool.min_stake_amount,
+        max_stake_per_wallet: stake_pool.max_stake_per_wallet,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    env.set_program_account(&pool.address, data, STAKE_POOL_DEFAULT_SIZE)
+        .await;
+}
+
+#[tokio::test]
+async fn v1_pool_is_rejected_until_migrated() {
+    let (mut env, pool) = setup_pool(anchor_spl::token::ID, 0, Vec::new()).await;
+    let admin = env.admin.insecure_clone();
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    write_v1_pool(&mut env, &pool).await;
+
+    // the V1 bytes fit the latest layout, only the version tells them apart
+    assert_error(
+        env.stake(&pool, &user, STAKE_AMOUNT, None).await,
+        ErrorCode::UnsupportedAccountVersion,
+    );
+    assert_error(
+        env.update_pool(
+            &pool,
+            &admin,
+            stake_pool::instruction::UpdatePoolPaused { paused: true },
+        )
+        .await,
+        ErrorCode::UnsupportedAccountVersion,
+    );
+
+    let payer = env.create_user().await;
+    env.migrate_pool(&pool, &payer).await.unwrap();
+    assert_eq!(env.stake_pool(&pool).await.version, STAKE_POOL_VERSION);
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+}
@@ -0,0 +1,297 @@
+mod common;
+
+use common::*;
+use solana_sdk::signature::Signer;
+use stake_pool::errors::ErrorCode;
+use stake_pool::instruction::{
+    CancelPoolParams, ProposePoolParams, UpdatePoolCampaign, UpdatePoolLimits, UpdatePoolLockTiers,
+    UpdatePoolReward, UpdatePoolRewardCurve, UpdatePoolUnbonding,
+};
+use stake_pool::{RewardCurve, StakePool, BASE_MULTIPLIER_BP, MAX_STAKE_REWARD};
+
+const DELAY: u32 = 86_400;
+const CURVE: RewardCurve = RewardCurve {
+    base_reward: 500,
+    slope_reward: 1500,
+    target_staked: STAKE_AMOUNT * 2,
+};
+
+async fn setup() -> (TestEnv, TestPool) {
+    let (mut env, pool) = setup_pool(anchor_spl::token::ID, 0, Vec::new()).await;
+    // without a delay the change is effective right away
+    let admin = env.admin.insecure_clone();
+    env.pool_params(
+        &pool,
+        &admin,
+        ProposePoolParams {
+            stake_reward: None,
+            min_stake_seconds: None,
+            param_change_delay: Some(DELAY),
+            reward_curve: None,
+        },
+    )
+    .await
+    .unwrap();
+    env.apply_pool_params(&pool).await.unwrap();
+    (env, pool)
+}
+
+#[tokio::test]
+async fn parameter_changes_wait_for_the_delay() {
+    let (mut env, pool) = setup().await;
+    let admin = env.admin.insecure_clone();
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    assert_eq!(stake_pool.param_change_delay, DELAY);
+    assert!(stake_pool.pending_params.is_none());
+
+    assert_error(
+        env.update_pool_as_authority(
+            &pool,
+            &admin,
+            UpdatePoolReward {
+                stake_reward: STAKE_REWARD * 2,
+            },
+        )
+        .await,
+        ErrorCode::ParamsTimelocked,
+    );
+    // outside the absolute bounds or more than halving / doubling the current rate
+    for stake_reward in [
+        MAX_STAKE_REWARD + 1,
+        STAKE_REWARD * 2 + 1,
+        STAKE_REWARD / 2 - 1,
+    ] {
+        assert_error(
+            env.pool_params(
+                &pool,
+                &admin,
+                ProposePoolParams {
+                    stake_reward: Some(stake_reward),
+                    min_stake_seconds: None,
+                    param_change_delay: None,
+                    reward_curve: None,
+                },
+            )
+            .await,
+            ErrorCode::InvalidStakeReward,
+        );
+    }
+    // campaign, lock tier, unbonding and limit changes are timelocked as well
+    assert_error(
+        env.update_pool_as_authority(
+            &pool,
+            &admin,
+            UpdatePoolCampaign {
+                rewards_start: 0,
+                rewards_end: 1,
+                total_reward_budget: 0,
+            },
+        )
+        .await,
+        ErrorCode::ParamsTimelocked,
+    );
+    assert_error(
+        env.update_pool(&pool, &admin, UpdatePoolLockTiers { lock_tiers: vec![] })
+            .await,
+        ErrorCode::ParamsTimelocked,
+    );
+    assert_error(
+        env.update_pool_as_authority(
+            &pool,
+            &admin,
+            UpdatePoolUnbonding {
+                unbonding_seconds: u32::MAX,
+            },
+        )
+        .await,
+        ErrorCode::ParamsTimelocked,
+    );
+    assert_error(
+        env.update_pool_as_authority(
+            &pool,
+            &admin,
+            UpdatePoolLimits {
+                max_total_staked: 1,
+                min_stake_amount: 0,
+                max_stake_per_wallet: 0,
+            },
+        )
+        .await,
+        ErrorCode::ParamsTimelocked,
+    );
+
+    env.pool_params(
+        &pool,
+        &admin,
+        ProposePoolParams {
+            stake_reward: Some(STAKE_REWARD * 2),
+            min_stake_seconds: Some(3600),
+            param_change_delay: None,
+            reward_curve: Some(Some(CURVE)),
+        },
+    )
+    .await
+    .unwrap();
+    let now = env.now().await;
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    let pending = stake_pool.pending_params.unwrap();
+    assert_eq!(pending.stake_reward, Some(STAKE_REWARD * 2));
+    assert_eq!(pending.min_stake_seconds, Some(3600));
+    assert!(pending.reward_curve == Some(Some(CURVE)));
+    assert_eq!(pending.effective_at, now + i64::from(DELAY));
+    assert_eq!(stake_pool.stake_reward, STAKE_REWARD);
+
+    assert_error(
+        env.apply_pool_params(&pool).await,
+        ErrorCode::ParamsNotEffective,
+    );
+    env.warp(i64::from(DELAY)).await;
+    env.apply_pool_params(&pool).await.unwrap();
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    assert_eq!(stake_pool.stake_reward, STAKE_REWARD * 2);
+    assert_eq!(stake_pool.min_stake_seconds, Some(3600));
+    assert_eq!(stake_pool.param_change_delay, DELAY);
+    assert!(stake_pool.reward_curve == Some(CURVE));
+    assert!(stake_pool.pending_params.is_none());
+    assert_error(
+        env.pool_params(&pool, &admin, UpdatePoolRewardCurve { reward_curve: None })
+            .await,
+        ErrorCode::ParamsTimelocked,
+    );
+}
+
+#[tokio::test]
+async fn pending_change_can_be_cancelled() {
+    let (mut env, pool) = setup().await;
+    let admin = env.admin.insecure_clone();
+    let user = env.create_user().await;
+    let propose = || ProposePoolParams {
+        stake_reward: Some(STAKE_REWARD / 2),
+        min_stake_seconds: None,
+        param_change_delay: Some(0),
+        reward_curve: Some(None),
+    };
+
+    assert_error(
+        env.pool_params(&pool, &user, propose()).await,
+        ErrorCode::InvalidAdmin,
+    );
+    env.pool_params(&pool, &admin, propose()).await.unwrap();
+    env.pool_params(&pool, &admin, CancelPoolParams {})
+        .await
+        .unwrap();
+
+    env.warp(i64::from(DELAY)).await;
+    assert_error(
+        env.apply_pool_params(&pool).await,
+        ErrorCode::NoPendingParams,
+    );
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    assert_eq!(stake_pool.stake_reward, STAKE_REWARD);
+    assert_eq!(stake_pool.param_change_delay, DELAY);
+}
+
+#[tokio::test]
+async fn reward_curve_follows_utilization() {
+    let (mut env, pool) = setup_pool(anchor_spl::token::ID, 0, Vec::new()).await;
+    let admin = env.admin.insecure_clone();
+    assert_error(
+        env.pool_params(
+            &pool,
+            &admin,
+            UpdatePoolRewardCurve {
+                reward_curve: Some(RewardCurve {
+                    slope_reward: MAX_STAKE_REWARD,
+                    ..CURVE
+                }),
+            },
+        )
+        .await,
+        ErrorCode::InvalidRewardCurve,
+    );
+    // both ends of the curve are bound by the step limit around the current rate
+    let too_low = RewardCurve {
+        base_reward: STAKE_REWARD / 2 - 1,
+        ..CURVE
+    };
+    let too_high = RewardCurve {
+        slope_reward: STAKE_REWARD * 2 - CURVE.base_reward + 1,
+        ..CURVE
+    };
+    for curve in [too_low, too_high] {
+        assert_error(
+            env.pool_params(
+                &pool,
+                &admin,
+                UpdatePoolRewardCurve {
+                    reward_curve: Some(curve),
+                },
+            )
+            .await,
+            ErrorCode::InvalidRewardCurve,
+        );
+        assert_error(
+            env.pool_params(
+                &pool,
+                &admin,
+                ProposePoolParams {
+                    stake_reward: None,
+                    min_stake_seconds: None,
+                    param_change_delay: None,
+                    reward_curve: Some(Some(curve)),
+                },
+            )
+            .await,
+            ErrorCode::InvalidRewardCurve,
+        );
+    }
+    env.pool_params(
+        &pool,
+        &admin,
+        UpdatePoolRewardCurve {
+            reward_curve: Some(CURVE),
+        },
+    )
+    .await
+    .unwrap();
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    assert_eq!(stake_pool.current_stake_reward().unwrap(), 2000);
+
+    // half of the target staked pays half of the slope on top of the base
+    let alice = env.create_user().await;
+    let bob = env.create_user().await;
+    let alice_token_account = env.fund(&alice.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.fund(&bob.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &alice).await;
+    env.init_entry(&pool, &bob).await;
+    env.stake(&pool, &alice, STAKE_AMOUNT, None).await.unwrap();
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    assert_eq!(stake_pool.current_stake_reward().unwrap(), 1250);
+
+    env.warp(SECONDS_IN_YEAR / 2).await;
+    env.claim(&pool, &alice).await.unwrap();
+    let rewards = env.balance(&alice_token_account).await;
+    assert_eq!(
+        rewards,
+        expected_rewards(STAKE_AMOUNT, 1250, SECONDS_IN_YEAR / 2, BASE_MULTIPLIER_BP)
+    );
+
+    // at the target the curve is flat at the base rate
+    env.stake(&pool, &bob, STAKE_AMOUNT, None).await.unwrap();
+    let stake_pool: StakePool = env.account(&pool.address).await;
+    assert_eq!(
+        stake_pool.current_stake_reward().unwrap(),
+        CURVE.base_reward
+    );
+    env.warp(SECONDS_IN_YEAR / 2).await;
+    env.claim(&pool, &alice).await.unwrap();
+    assert_eq!(
+        env.balance(&alice_token_account).await - rewards,
+        expected_rewards(
+            STAKE_AMOUNT,
+            CURVE.base_reward,
+            SECONDS_IN_YEAR / 2,
+            BASE_MULTIPLIER_BP
+        )
+    );
+}
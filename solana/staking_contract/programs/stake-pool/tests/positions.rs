@@ -0,0 +1,138 @@
+mod common;
+
+use common::*;
+use solana_sdk::signature::Signer;
+use stake_pool::errors::ErrorCode;
+use stake_pool::{LockTier, StakerRegistry};
+
+const LOCK_SECONDS: u32 = 86_400;
+
+async fn setup() -> (TestEnv, TestPool) {
+    let lock_tier = LockTier {
+        lock_seconds: LOCK_SECONDS,
+        multiplier_bp: 20000,
+    };
+    setup_pool(anchor_spl::token::ID, 0, vec![lock_tier]).await
+}
+
+#[tokio::test]
+async fn positions_are_numbered_and_independent() {
+    let (mut env, pool) = setup().await;
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT * 3).await;
+
+    let first = env.open_position(&pool, &user).await;
+    let second = env.open_position(&pool, &user).await;
+    assert_eq!(
+        first,
+        StakerRegistry::position_address(&pool.address, &user.pubkey(), 0)
+    );
+    assert_eq!(
+        second,
+        StakerRegistry::position_address(&pool.address, &user.pubkey(), 1)
+    );
+    let registry: StakerRegistry = env.account(&env.registry(&pool, &user.pubkey())).await;
+    assert_eq!(registry.position_count, 2);
+    assert_eq!(registry.staker, user.pubkey());
+
+    // the locked position starts its lock now, the other one later and unlocked
+    env.stake_into(&pool, &user, &first, STAKE_AMOUNT, Some(0))
+        .await
+        .unwrap();
+    env.warp(i64::from(LOCK_SECONDS) / 2).await;
+    env.stake_into(&pool, &user, &second, STAKE_AMOUNT * 2, None)
+        .await
+        .unwrap();
+
+    let summary = env
+        .aggregate_positions(&pool, &user.pubkey(), 0, &[first, second])
+        .await
+        .unwrap();
+    assert_eq!(summary.open_positions, 2);
+    assert_eq!(summary.amount, STAKE_AMOUNT * 3);
+    assert_eq!(summary.locked_amount, STAKE_AMOUNT);
+    assert_eq!(
+        summary.pending_rewards,
+        expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD,
+            i64::from(LOCK_SECONDS) / 2,
+            20000
+        )
+    );
+
+    assert_error(
+        env.unstake_from(&pool, &user, &first, false).await,
+        ErrorCode::MinStakeSecondsNotSatisfied,
+    );
+    env.unstake_from(&pool, &user, &second, true).await.unwrap();
+    // no time passed for the second position, it earned nothing yet
+    assert_eq!(env.balance(&user_token_account).await, STAKE_AMOUNT * 2);
+
+    // closed positions are skipped but keep their index
+    env.warp(i64::from(LOCK_SECONDS) / 2).await;
+    let third = env.open_position(&pool, &user).await;
+    assert_eq!(
+        third,
+        StakerRegistry::position_address(&pool.address, &user.pubkey(), 2)
+    );
+    let summary = env
+        .aggregate_positions(&pool, &user.pubkey(), 0, &[first, second, third])
+        .await
+        .unwrap();
+    assert_eq!(summary.open_positions, 1);
+    assert_eq!(summary.amount, STAKE_AMOUNT);
+    assert_eq!(summary.locked_amount, 0);
+    env.unstake_from(&pool, &user, &first, false).await.unwrap();
+}
+
+#[tokio::test]
+async fn aggregate_pages_positions_in_order() {
+    let (mut env, pool) = setup().await;
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT * 3).await;
+    let first = env.open_position(&pool, &user).await;
+    let second = env.open_position(&pool, &user).await;
+    env.stake_into(&pool, &user, &first, STAKE_AMOUNT, None)
+        .await
+        .unwrap();
+    env.stake_into(&pool, &user, &second, STAKE_AMOUNT * 2, None)
+        .await
+        .unwrap();
+
+    for (start, positions) in [
+        (0, vec![second, first]),
+        (0, vec![first, first]),
+        (1, vec![first]),
+        (1, vec![second, first]),
+        (0, vec![]),
+    ] {
+        assert_error(
+            env.aggregate_positions(&pool, &user.pubkey(), start, &positions)
+                .await
+                .map(|_| ()),
+            ErrorCode::InvalidPositionAccounts,
+        );
+    }
+
+    // pages add up to the whole registry
+    let whole = env
+        .aggregate_positions(&pool, &user.pubkey(), 0, &[first, second])
+        .await
+        .unwrap();
+    let first_page = env
+        .aggregate_positions(&pool, &user.pubkey(), 0, &[first])
+        .await
+        .unwrap();
+    let second_page = env
+        .aggregate_positions(&pool, &user.pubkey(), 1, &[second])
+        .await
+        .unwrap();
+    assert_eq!(first_page.amount, STAKE_AMOUNT);
+    assert_eq!(second_page.amount, STAKE_AMOUNT * 2);
+    assert_eq!(
+        first_page.open_positions + second_page.open_positions,
+        whole.open_positions
+    );
+    assert_eq!(first_page.amount + second_page.amount, whole.amount);
+}
@@ -0,0 +1,1122 @@
+mod common;
+
+use common::*;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use stake_pool::errors::ErrorCode;
+use stake_pool::{InitPoolIx, BASE_MULTIPLIER_BP};
+
+const MIN_STAKE_SECONDS: u32 = 3600;
+const UNBONDING_SECONDS: u32 = 7200;
+
+fn token_programs() -> [Pubkey; 2] {
+    [anchor_spl::token::ID, spl_token_2022::ID]
+}
+
+async fn setup(token_program: Pubkey) -> (TestEnv, TestPool) {
+    setup_pool(token_program, MIN_STAKE_SECONDS, Vec::new()).await
+}
+
+#[tokio::test]
+async fn init_pool() {
+    for token_program in token_programs() {
+        let (mut env, pool) = setup(token_program).await;
+
+        let stake_pool = env.stake_pool(&pool).await;
+        assert_eq!(stake_pool.authority, env.admin.pubkey());
+        assert_eq!(stake_pool.mint, pool.mint);
+        assert_eq!(stake_pool.reward_mint, pool.reward_mint);
+        assert_eq!(stake_pool.stake_reward, STAKE_REWARD);
+        assert_eq!(stake_pool.min_stake_seconds, Some(MIN_STAKE_SECONDS));
+        assert_eq!(stake_pool.reward_reserve, REWARD_DEPOSIT);
+        assert_eq!(stake_pool.total_staked, 0);
+        assert_eq!(env.balance(&pool.reward_vault(&env)).await, REWARD_DEPOSIT);
+    }
+}
+
+#[tokio::test]
+async fn init_pool_requires_config_admin() {
+    let mut env = TestEnv::new(anchor_spl::token::ID).await;
+    let mint = env.create_mint(None).await;
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &mint, REWARD_DEPOSIT).await;
+
+    let ix = env.init_pool_ix(
+        &user.pubkey(),
+        "pool",
+        &mint,
+        &mint,
+        InitPoolIx {
+            min_stake_seconds: MIN_STAKE_SECONDS,
+            stake_reward: STAKE_REWARD,
+            deposite_amount: REWARD_DEPOSIT,
+            identifier: "pool".to_string(),
+        },
+    );
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::InvalidAdmin);
+}
+
+#[tokio::test]
+async fn stake_and_unstake_after_min_stake_time() {
+    for token_program in token_programs() {
+        let (mut env, pool) = setup(token_program).await;
+        let user = env.create_user().await;
+        let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+        let stake_entry = env.init_entry(&pool, &user).await;
+
+        env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+        assert_eq!(env.balance(&user_token_account).await, 0);
+        let entry = env.stake_entry(&stake_entry).await;
+        assert_eq!(entry.amount, STAKE_AMOUNT);
+        assert_eq!(entry.staker, user.pubkey());
+        let stake_pool = env.stake_pool(&pool).await;
+        assert_eq!(stake_pool.total_staked, STAKE_AMOUNT);
+        assert_eq!(stake_pool.total_stakers, 1);
+
+        env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+        env.unstake(&pool, &user, false).await.unwrap();
+
+        let rewards = expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD,
+            i64::from(MIN_STAKE_SECONDS),
+            BASE_MULTIPLIER_BP,
+        );
+        assert!(rewards > 0);
+        assert_eq!(
+            env.balance(&user_token_account).await,
+            STAKE_AMOUNT + rewards
+        );
+        assert_eq!(env.stake_entry(&stake_entry).await.amount, 0);
+        let stake_pool = env.stake_pool(&pool).await;
+        assert_eq!(stake_pool.total_staked, 0);
+        assert_eq!(stake_pool.total_stakers, 0);
+        assert_eq!(stake_pool.reward_reserve, REWARD_DEPOSIT - rewards);
+    }
+}
+
+#[tokio::test]
+async fn claim_rewards_on_both_token_programs() {
+    for token_program in token_programs() {
+        let (mut env, pool) = setup(token_program).await;
+        let user = env.create_user().await;
+        let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+        env.init_entry(&pool, &user).await;
+        env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+        env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+        env.claim(&pool, &user).await.unwrap();
+        let rewards = expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD,
+            i64::from(MIN_STAKE_SECONDS),
+            BASE_MULTIPLIER_BP,
+        );
+        assert_eq!(env.balance(&user_token_account).await, rewards);
+        assert_eq!(
+            env.stake_pool(&pool).await.reward_reserve,
+            REWARD_DEPOSIT - rewards
+        );
+    }
+}
+
+#[tokio::test]
+async fn init_pool_rejects_other_token_program() {
+    let mut env = TestEnv::new(anchor_spl::token::ID).await;
+    let mint = env.create_mint(None).await;
+    let admin = env.admin.insecure_clone();
+    env.fund(&admin.pubkey(), &mint, REWARD_DEPOSIT).await;
+
+    // a legacy mint cannot be driven through Token-2022
+    let mut ix = env.init_pool_ix(
+        &admin.pubkey(),
+        "pool",
+        &mint,
+        &mint,
+        InitPoolIx {
+            min_stake_seconds: MIN_STAKE_SECONDS,
+            stake_reward: STAKE_REWARD,
+            deposite_amount: REWARD_DEPOSIT,
+            identifier: "pool".to_string(),
+        },
+    );
+    for account in ix.accounts.iter_mut() {
+        if account.pubkey == anchor_spl::token::ID {
+            account.pubkey = spl_token_2022::ID;
+        }
+    }
+    assert!(env.send(&[ix], &[&admin]).await.is_err());
+}
+
+#[tokio::test]
+async fn unstake_before_min_stake_time_fails() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    env.warp(i64::from(MIN_STAKE_SECONDS) - 1).await;
+    assert_error(
+        env.unstake(&pool, &user, false).await,
+        ErrorCode::MinStakeSecondsNotSatisfied,
+    );
+
+    env.warp(1).await;
+    env.unstake(&pool, &user, false).await.unwrap();
+}
+
+#[tokio::test]
+async fn unstaking_an_empty_entry_fails() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let alice = env.create_user().await;
+    let bob = env.create_user().await;
+    for user in [&alice, &bob] {
+        env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+        env.init_entry(&pool, user).await;
+        env.stake(&pool, user, STAKE_AMOUNT, None).await.unwrap();
+    }
+
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    env.unstake(&pool, &alice, false).await.unwrap();
+    assert_eq!(env.stake_pool(&pool).await.total_stakers, 1);
+    // repeating the unstake must not count bob out of the pool
+    env.warp(1).await;
+    assert_error(
+        env.unstake(&pool, &alice, false).await,
+        ErrorCode::InvalidInput,
+    );
+    assert_eq!(env.stake_pool(&pool).await.total_stakers, 1);
+}
+
+#[tokio::test]
+async fn unstake_with_unbonding_releases_after_the_period() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolUnbonding {
+            unbonding_seconds: UNBONDING_SECONDS,
+        },
+    )
+    .await
+    .unwrap();
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    env.unstake(&pool, &user, false).await.unwrap();
+    let rewards = expected_rewards(
+        STAKE_AMOUNT,
+        STAKE_REWARD,
+        i64::from(MIN_STAKE_SECONDS),
+        BASE_MULTIPLIER_BP,
+    );
+    // rewards are paid up to the request, the principal waits
+    assert_eq!(env.balance(&user_token_account).await, rewards);
+    let entry = env.stake_entry(&pool.entry(&user.pubkey())).await;
+    assert_eq!(entry.unbonding_amount, STAKE_AMOUNT);
+    assert_eq!(
+        entry.withdrawable_at,
+        env.now().await + i64::from(UNBONDING_SECONDS)
+    );
+
+    env.warp(i64::from(UNBONDING_SECONDS) - 1).await;
+    assert_error(
+        env.complete_withdrawal(&pool, &user).await,
+        ErrorCode::UnbondingNotOver,
+    );
+    env.warp(1).await;
+    env.complete_withdrawal(&pool, &user).await.unwrap();
+    assert_eq!(
+        env.balance(&user_token_account).await,
+        STAKE_AMOUNT + rewards
+    );
+    let entry = env.stake_entry(&pool.entry(&user.pubkey())).await;
+    assert_eq!(entry.unbonding_amount, 0);
+    assert_eq!(entry.pending_rewards, 0);
+    let stake_pool = env.stake_pool(&pool).await;
+    assert_eq!(stake_pool.total_unbonding, 0);
+    assert_eq!(stake_pool.reward_reserve, REWARD_DEPOSIT - rewards);
+    assert_error(
+        env.complete_withdrawal(&pool, &user).await,
+        ErrorCode::NothingToWithdraw,
+    );
+}
+
+#[tokio::test]
+async fn unstake_and_close_fails_while_unbonding() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolUnbonding {
+            unbonding_seconds: 3600,
+        },
+    )
+    .await
+    .unwrap();
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    assert_error(
+        env.unstake(&pool, &user, true).await,
+        ErrorCode::EntryUnbonding,
+    );
+    env.unstake(&pool, &user, false).await.unwrap();
+    let entry = env.stake_entry(&pool.entry(&user.pubkey())).await;
+    assert_eq!(entry.amount, 0);
+    assert_eq!(entry.unbonding_amount, STAKE_AMOUNT);
+}
+
+#[tokio::test]
+async fn unstake_and_close_returns_the_rent() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    let stake_entry = env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+    let rent = env.lamports(&stake_entry).await;
+    let lamports = env.lamports(&user.pubkey()).await;
+
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    env.unstake(&pool, &user, true).await.unwrap();
+    assert_eq!(env.lamports(&stake_entry).await, 0);
+    assert_eq!(env.lamports(&user.pubkey()).await, lamports + rent);
+    let rewards = expected_rewards(
+        STAKE_AMOUNT,
+        STAKE_REWARD,
+        i64::from(MIN_STAKE_SECONDS),
+        BASE_MULTIPLIER_BP,
+    );
+    assert_eq!(
+        env.balance(&user_token_account).await,
+        STAKE_AMOUNT + rewards
+    );
+    assert_eq!(env.stake_pool(&pool).await.total_stakers, 0);
+
+    // the same seeds can be initialized and staked into again
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+    let entry = env.stake_entry(&stake_entry).await;
+    assert_eq!(entry.amount, STAKE_AMOUNT);
+    assert_eq!(entry.staker, user.pubkey());
+    assert_eq!(env.stake_pool(&pool).await.total_stakers, 1);
+}
+
+#[tokio::test]
+async fn close_entry_requires_an_empty_entry() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let user = env.create_user().await;
+    let other = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    let stake_entry = env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    assert_error(
+        env.close_entry(&user, &stake_entry).await,
+        ErrorCode::StakeEntryNotEmpty,
+    );
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    env.unstake(&pool, &user, false).await.unwrap();
+    assert_error(
+        env.close_entry(&other, &stake_entry).await,
+        ErrorCode::InvalidStakeEntryOwner,
+    );
+
+    let rent = env.lamports(&stake_entry).await;
+    let lamports = env.lamports(&user.pubkey()).await;
+    env.close_entry(&user, &stake_entry).await.unwrap();
+    assert_eq!(env.lamports(&stake_entry).await, 0);
+    assert_eq!(env.lamports(&user.pubkey()).await, lamports + rent);
+}
+
+#[tokio::test]
+async fn receipts_are_required_to_redeem_and_claim() {
+    let (mut env, mut pool) = setup(anchor_spl::token::ID).await;
+    env.init_receipt_mint(&mut pool).await;
+    let receipt_mint = pool.receipt_mint.unwrap();
+    let alice = env.create_user().await;
+    let bob = env.create_user().await;
+    env.fund(&alice.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.fund(&alice.pubkey(), &receipt_mint, 0).await;
+    env.fund(&bob.pubkey(), &receipt_mint, 0).await;
+    env.init_entry(&pool, &alice).await;
+    env.stake(&pool, &alice, STAKE_AMOUNT, None).await.unwrap();
+    let alice_receipts = env.ata(&alice.pubkey(), &receipt_mint);
+    assert_eq!(env.balance(&alice_receipts).await, STAKE_AMOUNT);
+
+    // the receipt holder has no entry to redeem against
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    env.transfer(&alice, &bob.pubkey(), &receipt_mint, STAKE_AMOUNT)
+        .await;
+    env.fund(&bob.pubkey(), &pool.mint, 0).await;
+    env.fund(&bob.pubkey(), &pool.reward_mint, 0).await;
+    let alice_entry = pool.entry(&alice.pubkey());
+    assert_error(
+        env.unstake_from(&pool, &bob, &alice_entry, false).await,
+        ErrorCode::InvalidStakeEntryOwner,
+    );
+    // and the seller can neither redeem nor collect rewards
+    assert!(env.unstake(&pool, &alice, false).await.is_err());
+    assert_error(env.claim(&pool, &alice).await, ErrorCode::ReceiptsNotHeld);
+
+    // with part of the receipts back, principal is redeemed but rewards are withheld
+    env.transfer(&bob, &alice.pubkey(), &receipt_mint, STAKE_AMOUNT / 2)
+        .await;
+    env.unstake_partial(&pool, &alice, STAKE_AMOUNT / 4)
+        .await
+        .unwrap();
+    // reward and stake mint are the same, only the principal arrived
+    let alice_tokens = env.ata(&alice.pubkey(), &pool.mint);
+    assert_eq!(env.balance(&alice_tokens).await, STAKE_AMOUNT / 4);
+    let pending_rewards = env.stake_entry(&alice_entry).await.pending_rewards;
+    assert!(pending_rewards > 0);
+
+    env.transfer(&bob, &alice.pubkey(), &receipt_mint, STAKE_AMOUNT / 2)
+        .await;
+    env.claim(&pool, &alice).await.unwrap();
+    assert!(env.balance(&alice_tokens).await >= STAKE_AMOUNT / 4 + pending_rewards);
+    env.unstake(&pool, &alice, false).await.unwrap();
+    assert_eq!(env.balance(&alice_receipts).await, 0);
+}
+
+#[tokio::test]
+async fn stake_below_minimum_amount_fails() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolLimits {
+            max_total_staked: 0,
+            min_stake_amount: STAKE_AMOUNT,
+            max_stake_per_wallet: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    assert_error(
+        env.stake(&pool, &user, STAKE_AMOUNT - 1, None).await,
+        ErrorCode::StakeBelowMinimum,
+    );
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn compound_respects_stake_caps() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolCompound {
+            compound_enabled: true,
+        },
+    )
+    .await
+    .unwrap();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolLimits {
+            max_total_staked: STAKE_AMOUNT,
+            min_stake_amount: 0,
+            max_stake_per_wallet: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+    env.warp(SECONDS_IN_YEAR / 2).await;
+    assert_error(env.compound(&pool, &user).await, ErrorCode::PoolCapExceeded);
+
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolLimits {
+            max_total_staked: 0,
+            min_stake_amount: 0,
+            max_stake_per_wallet: 0,
+        },
+    )
+    .await
+    .unwrap();
+    env.compound(&pool, &user).await.unwrap();
+    assert_eq!(
+        env.stake_pool(&pool).await.total_staked,
+        STAKE_AMOUNT + STAKE_AMOUNT / 20
+    );
+}
+
+#[tokio::test]
+async fn wallet_cap_spans_entries_positions_and_grants() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolCompound {
+            compound_enabled: true,
+        },
+    )
+    .await
+    .unwrap();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolLimits {
+            max_total_staked: 0,
+            min_stake_amount: 0,
+            max_stake_per_wallet: STAKE_AMOUNT,
+        },
+    )
+    .await
+    .unwrap();
+
+    let user = env.create_user().await;
+    let funder = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT * 2).await;
+    env.fund(&funder.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT / 2, None)
+        .await
+        .unwrap();
+
+    let position = env.open_position(&pool, &user).await;
+    assert_error(
+        env.stake_into(&pool, &user, &position, STAKE_AMOUNT / 2 + 1, None)
+            .await,
+        ErrorCode::WalletCapExceeded,
+    );
+    env.stake_into(&pool, &user, &position, STAKE_AMOUNT / 2, None)
+        .await
+        .unwrap();
+    assert_error(
+        env.stake_for(&pool, &funder, &user.pubkey(), 0, 1, None, 0, 0)
+            .await
+            .map(|_| ()),
+        ErrorCode::WalletCapExceeded,
+    );
+    env.warp(SECONDS_IN_YEAR / 2).await;
+    assert_error(
+        env.compound(&pool, &user).await,
+        ErrorCode::WalletCapExceeded,
+    );
+
+    // unstaking frees room under the cap
+    env.unstake_partial_from(&pool, &user, &position, STAKE_AMOUNT / 4)
+        .await
+        .unwrap();
+    env.stake_for(
+        &pool,
+        &funder,
+        &user.pubkey(),
+        0,
+        STAKE_AMOUNT / 4,
+        None,
+        0,
+        0,
+    )
+    .await
+    .unwrap();
+    let registry: stake_pool::StakerRegistry =
+        env.account(&env.registry(&pool, &user.pubkey())).await;
+    assert_eq!(registry.staked_amount, STAKE_AMOUNT);
+}
+
+#[tokio::test]
+async fn early_unstake_keeps_deferred_rewards() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolEarlyUnstake {
+            penalty_bp: Some(1000),
+            penalty_treasury: None,
+        },
+    )
+    .await
+    .unwrap();
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    // a short reserve defers most of the earned rewards
+    env.warp(i64::from(MIN_STAKE_SECONDS) / 2).await;
+    env.withdraw(&pool, &admin, REWARD_DEPOSIT - 1)
+        .await
+        .unwrap();
+    env.claim(&pool, &user).await.unwrap();
+    let deferred = env
+        .stake_entry(&pool.entry(&user.pubkey()))
+        .await
+        .pending_rewards;
+    assert!(deferred > 0);
+
+    env.warp(1).await;
+    env.early_unstake(&pool, &user, None).await.unwrap();
+    let entry = env.stake_entry(&pool.entry(&user.pubkey())).await;
+    assert_eq!(entry.amount, 0);
+    assert_eq!(entry.pending_rewards, deferred);
+    assert_eq!(
+        env.balance(&env.ata(&user.pubkey(), &pool.mint)).await,
+        1 + STAKE_AMOUNT - STAKE_AMOUNT / 10
+    );
+}
+
+#[tokio::test]
+async fn early_unstake_goes_through_unbonding() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolEarlyUnstake {
+            penalty_bp: Some(0),
+            penalty_treasury: None,
+        },
+    )
+    .await
+    .unwrap();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolUnbonding {
+            unbonding_seconds: UNBONDING_SECONDS,
+        },
+    )
+    .await
+    .unwrap();
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    env.early_unstake(&pool, &user, None).await.unwrap();
+    assert_eq!(env.balance(&user_token_account).await, 0);
+    let entry = env.stake_entry(&pool.entry(&user.pubkey())).await;
+    assert_eq!(entry.amount, 0);
+    assert_eq!(entry.unbonding_amount, STAKE_AMOUNT);
+    assert_eq!(env.stake_pool(&pool).await.total_unbonding, STAKE_AMOUNT);
+    assert_error(
+        env.complete_withdrawal(&pool, &user).await,
+        ErrorCode::UnbondingNotOver,
+    );
+
+    env.warp(i64::from(UNBONDING_SECONDS)).await;
+    env.complete_withdrawal(&pool, &user).await.unwrap();
+    assert_eq!(env.balance(&user_token_account).await, STAKE_AMOUNT);
+    assert_eq!(env.stake_pool(&pool).await.total_unbonding, 0);
+}
+
+#[tokio::test]
+async fn early_unstake_treasury_must_hold_the_stake_mint() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    let other_mint = env.create_mint(None).await;
+    let treasury = env.fund(&admin.pubkey(), &other_mint, 0).await;
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolEarlyUnstake {
+            penalty_bp: Some(1000),
+            penalty_treasury: Some(treasury),
+        },
+    )
+    .await
+    .unwrap();
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    assert_error(
+        env.early_unstake(&pool, &user, Some(treasury)).await,
+        ErrorCode::InvalidPenaltyTreasury,
+    );
+}
+
+#[tokio::test]
+async fn authority_transfer_resets_the_operator() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    let new_authority = env.create_user().await;
+    env.transfer_pool_authority(&pool, &admin, &new_authority)
+        .await
+        .unwrap();
+
+    let stake_pool = env.stake_pool(&pool).await;
+    assert_eq!(stake_pool.authority, new_authority.pubkey());
+    assert_eq!(stake_pool.operator, new_authority.pubkey());
+    assert_error(
+        env.update_pool(
+            &pool,
+            &admin,
+            stake_pool::instruction::UpdatePoolPaused { paused: true },
+        )
+        .await,
+        ErrorCode::InvalidAdmin,
+    );
+    env.update_pool(
+        &pool,
+        &new_authority,
+        stake_pool::instruction::UpdatePoolPaused { paused: true },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn operator_cannot_change_economic_settings() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    let operator = env.create_user().await;
+    env.set_pool_operator(&pool, &admin, &operator.pubkey())
+        .await
+        .unwrap();
+
+    assert_error(
+        env.update_pool_as_authority(
+            &pool,
+            &operator,
+            stake_pool::instruction::UpdatePoolEarlyUnstake {
+                penalty_bp: Some(10000),
+                penalty_treasury: Some(operator.pubkey()),
+            },
+        )
+        .await,
+        ErrorCode::InvalidAdmin,
+    );
+    assert_error(
+        env.update_pool_as_authority(
+            &pool,
+            &operator,
+            stake_pool::instruction::UpdatePoolCampaign {
+                rewards_start: 0,
+                rewards_end: 1,
+                total_reward_budget: 0,
+            },
+        )
+        .await,
+        ErrorCode::InvalidAdmin,
+    );
+    env.update_pool(
+        &pool,
+        &operator,
+        stake_pool::instruction::UpdatePoolPaused { paused: true },
+    )
+    .await
+    .unwrap();
+    assert!(env.stake_pool(&pool).await.paused);
+}
+
+#[tokio::test]
+async fn rewards_accrue_at_stake_reward_rate() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    // 10% APR over half a year
+    env.warp(SECONDS_IN_YEAR / 2).await;
+    env.claim(&pool, &user).await.unwrap();
+    let rewards = env.balance(&user_token_account).await;
+    assert_eq!(
+        rewards,
+        expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD,
+            SECONDS_IN_YEAR / 2,
+            BASE_MULTIPLIER_BP
+        )
+    );
+    assert_eq!(rewards, STAKE_AMOUNT / 20);
+
+    // the rate change only applies going forward
+    let admin = env.admin.insecure_clone();
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolReward {
+            stake_reward: STAKE_REWARD * 2,
+        },
+    )
+    .await
+    .unwrap();
+    env.warp(SECONDS_IN_YEAR / 4).await;
+    env.claim(&pool, &user).await.unwrap();
+    assert_eq!(
+        env.balance(&user_token_account).await - rewards,
+        expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD * 2,
+            SECONDS_IN_YEAR / 4,
+            BASE_MULTIPLIER_BP
+        )
+    );
+}
+
+#[tokio::test]
+async fn rewards_are_split_by_stake() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let alice = env.create_user().await;
+    let bob = env.create_user().await;
+    let alice_account = env.fund(&alice.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    let bob_account = env.fund(&bob.pubkey(), &pool.mint, STAKE_AMOUNT * 3).await;
+    env.init_entry(&pool, &alice).await;
+    env.init_entry(&pool, &bob).await;
+
+    env.stake(&pool, &alice, STAKE_AMOUNT, None).await.unwrap();
+    env.warp(SECONDS_IN_YEAR / 10).await;
+    env.stake(&pool, &bob, STAKE_AMOUNT * 3, None)
+        .await
+        .unwrap();
+    env.warp(SECONDS_IN_YEAR / 10).await;
+
+    env.claim(&pool, &alice).await.unwrap();
+    env.claim(&pool, &bob).await.unwrap();
+    assert_eq!(
+        env.balance(&alice_account).await,
+        expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD,
+            SECONDS_IN_YEAR / 5,
+            BASE_MULTIPLIER_BP
+        )
+    );
+    assert_eq!(
+        env.balance(&bob_account).await,
+        expected_rewards(
+            STAKE_AMOUNT * 3,
+            STAKE_REWARD,
+            SECONDS_IN_YEAR / 10,
+            BASE_MULTIPLIER_BP
+        )
+    );
+}
+
+#[tokio::test]
+async fn lock_tier_boosts_rewards() {
+    let (mut env, pool) = setup(spl_token_2022::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolLockTiers {
+            lock_tiers: vec![stake_pool::LockTier {
+                lock_seconds: 86_400,
+                multiplier_bp: 15000,
+            }],
+        },
+    )
+    .await
+    .unwrap();
+
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, Some(0))
+        .await
+        .unwrap();
+
+    env.warp(86_399).await;
+    assert_error(
+        env.unstake(&pool, &user, false).await,
+        ErrorCode::MinStakeSecondsNotSatisfied,
+    );
+    env.warp(1).await;
+    env.unstake(&pool, &user, false).await.unwrap();
+    assert_eq!(
+        env.balance(&user_token_account).await,
+        STAKE_AMOUNT + expected_rewards(STAKE_AMOUNT, STAKE_REWARD, 86_400, 15000)
+    );
+}
+
+#[tokio::test]
+async fn rewards_stop_at_the_campaign_end() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    let campaign_seconds = 86_400;
+    let rewards_end = env.now().await + campaign_seconds;
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolCampaign {
+            rewards_start: 0,
+            rewards_end,
+            total_reward_budget: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    env.warp(campaign_seconds * 2).await;
+    env.claim(&pool, &user).await.unwrap();
+    assert_eq!(
+        env.balance(&user_token_account).await,
+        expected_rewards(
+            STAKE_AMOUNT,
+            STAKE_REWARD,
+            campaign_seconds,
+            BASE_MULTIPLIER_BP
+        )
+    );
+}
+
+#[tokio::test]
+async fn rewards_stop_once_the_budget_is_spent() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolLockTiers {
+            lock_tiers: vec![stake_pool::LockTier {
+                lock_seconds: 86_400,
+                multiplier_bp: 15000,
+            }],
+        },
+    )
+    .await
+    .unwrap();
+    // a year would pay 1.5x to alice and 1x to bob, the budget covers 0.4 years
+    let budget = expected_rewards(STAKE_AMOUNT, STAKE_REWARD, SECONDS_IN_YEAR, 25000) * 2 / 5;
+    env.update_pool_as_authority(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolCampaign {
+            rewards_start: 0,
+            rewards_end: 0,
+            total_reward_budget: budget,
+        },
+    )
+    .await
+    .unwrap();
+    let alice = env.create_user().await;
+    let bob = env.create_user().await;
+    let alice_token_account = env.fund(&alice.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    let bob_token_account = env.fund(&bob.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &alice).await;
+    env.init_entry(&pool, &bob).await;
+    env.stake(&pool, &alice, STAKE_AMOUNT, Some(0))
+        .await
+        .unwrap();
+    env.stake(&pool, &bob, STAKE_AMOUNT, None).await.unwrap();
+
+    env.warp(SECONDS_IN_YEAR).await;
+    env.claim(&pool, &alice).await.unwrap();
+    env.claim(&pool, &bob).await.unwrap();
+    assert_eq!(env.stake_pool(&pool).await.remaining_reward_budget, 0);
+    let alice_rewards = env.balance(&alice_token_account).await;
+    let bob_rewards = env.balance(&bob_token_account).await;
+    // the budget is split by boosted stake, rounding down
+    assert!(alice_rewards + bob_rewards <= budget);
+    assert!(alice_rewards + bob_rewards >= budget - 2);
+    assert!(alice_rewards.abs_diff(bob_rewards * 3 / 2) <= 1);
+}
+
+#[tokio::test]
+async fn withdraw_is_authority_only() {
+    for token_program in token_programs() {
+        let (mut env, pool) = setup(token_program).await;
+        let admin = env.admin.insecure_clone();
+        let user = env.create_user().await;
+        env.fund(&user.pubkey(), &pool.reward_mint, 0).await;
+
+        assert_error(env.withdraw(&pool, &user, 1).await, ErrorCode::InvalidAdmin);
+        assert_error(
+            env.withdraw(&pool, &admin, REWARD_DEPOSIT + 1).await,
+            ErrorCode::InsufficientRewardReserve,
+        );
+
+        let admin_account = env.ata(&admin.pubkey(), &pool.reward_mint);
+        let before = env.balance(&admin_account).await;
+        env.withdraw(&pool, &admin, REWARD_DEPOSIT / 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            env.balance(&admin_account).await,
+            before + REWARD_DEPOSIT / 2
+        );
+        assert_eq!(
+            env.stake_pool(&pool).await.reward_reserve,
+            REWARD_DEPOSIT / 2
+        );
+    }
+}
+
+#[tokio::test]
+async fn withdraw_cannot_take_staked_principal() {
+    let (mut env, pool) = setup(anchor_spl::token::ID).await;
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    let admin = env.admin.insecure_clone();
+    assert_error(
+        env.withdraw(&pool, &admin, REWARD_DEPOSIT + STAKE_AMOUNT)
+            .await,
+        ErrorCode::InsufficientRewardReserve,
+    );
+}
+
+#[tokio::test]
+async fn deposite_is_authority_only() {
+    for token_program in token_programs() {
+        let (mut env, pool) = setup(token_program).await;
+        let admin = env.admin.insecure_clone();
+        let user = env.create_user().await;
+        env.fund(&user.pubkey(), &pool.reward_mint, REWARD_DEPOSIT)
+            .await;
+
+        assert_error(
+            env.deposite(&pool, &user, REWARD_DEPOSIT).await,
+            ErrorCode::InvalidAdmin,
+        );
+
+        env.deposite(&pool, &admin, REWARD_DEPOSIT).await.unwrap();
+        assert_eq!(
+            env.stake_pool(&pool).await.reward_reserve,
+            REWARD_DEPOSIT * 2
+        );
+        assert_eq!(
+            env.balance(&pool.reward_vault(&env)).await,
+            REWARD_DEPOSIT * 2
+        );
+    }
+}
+
+#[tokio::test]
+async fn transfer_fees_are_borne_by_the_user() {
+    let fee = TransferFee {
+        basis_points: 100,
+        maximum_fee: u64::MAX,
+    };
+    let mut env = TestEnv::new(spl_token_2022::ID).await;
+    let mint = env.create_mint(Some(fee)).await;
+    let pool = env
+        .create_pool(&mint, MIN_STAKE_SECONDS, STAKE_REWARD, REWARD_DEPOSIT)
+        .await;
+    // the depositor pays the inbound fee, the reserve matches the vault
+    assert_eq!(env.balance(&pool.reward_vault(&env)).await, REWARD_DEPOSIT);
+
+    let user = env.create_user().await;
+    let user_token_account = env.fund(&user.pubkey(), &mint, STAKE_AMOUNT * 2).await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+
+    let stake_fee = STAKE_AMOUNT * 2 - STAKE_AMOUNT - env.balance(&user_token_account).await;
+    assert!(stake_fee > 0);
+    assert_eq!(fee.calculate(STAKE_AMOUNT + stake_fee), stake_fee);
+    assert_eq!(
+        env.balance(&pool.pool_token_account(&env)).await,
+        REWARD_DEPOSIT + STAKE_AMOUNT
+    );
+
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    let before = env.balance(&user_token_account).await;
+    env.unstake(&pool, &user, false).await.unwrap();
+
+    // the pool is debited exactly what it owes, the user receives it net of fees
+    let rewards = expected_rewards(
+        STAKE_AMOUNT,
+        STAKE_REWARD,
+        i64::from(MIN_STAKE_SECONDS),
+        BASE_MULTIPLIER_BP,
+    );
+    let stake_pool = env.stake_pool(&pool).await;
+    assert_eq!(stake_pool.reward_reserve, REWARD_DEPOSIT - rewards);
+    assert_eq!(
+        env.balance(&pool.reward_vault(&env)).await,
+        stake_pool.reward_reserve + stake_pool.total_staked
+    );
+    assert_eq!(
+        env.balance(&user_token_account).await - before,
+        STAKE_AMOUNT - fee.calculate(STAKE_AMOUNT) + rewards - fee.calculate(rewards)
+    );
+}
+
+#[tokio::test]
+async fn capped_transfer_fees_on_claim_and_withdraw() {
+    let fee = TransferFee {
+        basis_points: 100,
+        maximum_fee: 1_000,
+    };
+    let mut env = TestEnv::new(spl_token_2022::ID).await;
+    let mint = env.create_mint(Some(fee)).await;
+    let pool = env
+        .create_pool(&mint, MIN_STAKE_SECONDS, STAKE_REWARD, REWARD_DEPOSIT)
+        .await;
+    assert_eq!(env.balance(&pool.reward_vault(&env)).await, REWARD_DEPOSIT);
+
+    // the gross-up stops at the maximum fee
+    let user = env.create_user().await;
+    let user_token_account = env
+        .fund(&user.pubkey(), &mint, STAKE_AMOUNT + fee.maximum_fee)
+        .await;
+    env.init_entry(&pool, &user).await;
+    env.stake(&pool, &user, STAKE_AMOUNT, None).await.unwrap();
+    assert_eq!(env.balance(&user_token_account).await, 0);
+
+    env.warp(i64::from(MIN_STAKE_SECONDS)).await;
+    env.claim(&pool, &user).await.unwrap();
+    let rewards = expected_rewards(
+        STAKE_AMOUNT,
+        STAKE_REWARD,
+        i64::from(MIN_STAKE_SECONDS),
+        BASE_MULTIPLIER_BP,
+    );
+    assert_eq!(
+        env.balance(&user_token_account).await,
+        rewards - fee.calculate(rewards)
+    );
+    let stake_pool = env.stake_pool(&pool).await;
+    assert_eq!(stake_pool.reward_reserve, REWARD_DEPOSIT - rewards);
+    assert_eq!(
+        env.balance(&pool.reward_vault(&env)).await,
+        stake_pool.reward_reserve + stake_pool.total_staked
+    );
+
+    // withdrawals debit the vault by the amount, the authority bears the fee
+    let admin = env.admin.insecure_clone();
+    let admin_token_account = env.ata(&admin.pubkey(), &mint);
+    let before = env.balance(&admin_token_account).await;
+    env.withdraw(&pool, &admin, STAKE_AMOUNT).await.unwrap();
+    assert_eq!(
+        env.balance(&admin_token_account).await - before,
+        STAKE_AMOUNT - fee.maximum_fee
+    );
+    let stake_pool = env.stake_pool(&pool).await;
+    assert_eq!(
+        stake_pool.reward_reserve,
+        REWARD_DEPOSIT - rewards - STAKE_AMOUNT
+    );
+    assert_eq!(
+        env.balance(&pool.reward_vault(&env)).await,
+        stake_pool.reward_reserve + stake_pool.total_staked
+    );
+}
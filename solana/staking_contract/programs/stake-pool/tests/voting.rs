@@ -0,0 +1,247 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_program;
+use stake_pool::errors::ErrorCode;
+use stake_pool::{LockTier, VotingCheckpoint, VOTING_CHECKPOINT_PREFIX};
+
+const LOCK_SECONDS: i64 = 86_400;
+
+async fn setup() -> (TestEnv, TestPool) {
+    let lock_tier = LockTier {
+        lock_seconds: LOCK_SECONDS as u32,
+        multiplier_bp: 25000,
+    };
+    setup_pool(anchor_spl::token::ID, 0, vec![lock_tier]).await
+}
+
+fn entry_metas(entries: &[Pubkey]) -> impl Iterator<Item = AccountMeta> + '_ {
+    entries
+        .iter()
+        .map(|entry| AccountMeta::new_readonly(*entry, false))
+}
+
+async fn get_voting_power(
+    env: &mut TestEnv,
+    pool: &TestPool,
+    staker: &Pubkey,
+    entries: &[Pubkey],
+) -> Result<u64, BanksClientError> {
+    let mut accounts = stake_pool::accounts::GetVotingPowerCtx {
+        stake_pool: pool.address,
+    }
+    .to_account_metas(None);
+    accounts.extend(entry_metas(entries));
+    env.simulate(Instruction {
+        program_id: stake_pool::ID,
+        accounts,
+        data: stake_pool::instruction::GetVotingPower { staker: *staker }.data(),
+    })
+    .await
+}
+
+fn checkpoint_address(pool: &TestPool, staker: &Pubkey, snapshot_at: i64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            VOTING_CHECKPOINT_PREFIX.as_bytes(),
+            pool.address.as_ref(),
+            staker.as_ref(),
+            &snapshot_at.to_le_bytes(),
+        ],
+        &stake_pool::ID,
+    )
+    .0
+}
+
+async fn snapshot(
+    env: &mut TestEnv,
+    pool: &TestPool,
+    staker: &Keypair,
+    snapshot_at: i64,
+    entries: &[Pubkey],
+) -> Result<(), BanksClientError> {
+    let mut accounts = stake_pool::accounts::SnapshotVotingPowerCtx {
+        voting_checkpoint: checkpoint_address(pool, &staker.pubkey(), snapshot_at),
+        stake_pool: pool.address,
+        staker: staker.pubkey(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(entry_metas(entries));
+    let ix = Instruction {
+        program_id: stake_pool::ID,
+        accounts,
+        data: stake_pool::instruction::SnapshotVotingPower { snapshot_at }.data(),
+    };
+    env.send(&[ix], &[staker]).await
+}
+
+/// A staker with an unlocked entry and a position locked at 2.5x.
+async fn staker_with_positions(env: &mut TestEnv, pool: &TestPool) -> (Keypair, Vec<Pubkey>) {
+    let user = env.create_user().await;
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT * 2).await;
+    let entry = env.init_entry(pool, &user).await;
+    env.stake(pool, &user, STAKE_AMOUNT, None).await.unwrap();
+    let position = env.open_position(pool, &user).await;
+    env.stake_into(pool, &user, &position, STAKE_AMOUNT, Some(0))
+        .await
+        .unwrap();
+    (user, vec![entry, position])
+}
+
+#[tokio::test]
+async fn voting_power_weights_stake_by_lock_tier() {
+    let (mut env, pool) = setup().await;
+    let (user, entries) = staker_with_positions(&mut env, &pool).await;
+
+    let voting_power = get_voting_power(&mut env, &pool, &user.pubkey(), &entries)
+        .await
+        .unwrap();
+    assert_eq!(voting_power, STAKE_AMOUNT + STAKE_AMOUNT * 5 / 2);
+    assert_eq!(
+        get_voting_power(&mut env, &pool, &user.pubkey(), &entries[..1])
+            .await
+            .unwrap(),
+        STAKE_AMOUNT
+    );
+
+    // an entry can only be counted once and only for its own staker
+    let duplicated = [entries[0], entries[1], entries[0]];
+    assert_error(
+        get_voting_power(&mut env, &pool, &user.pubkey(), &duplicated)
+            .await
+            .map(|_| ()),
+        ErrorCode::InvalidVotingEntries,
+    );
+    let other = env.create_user().await;
+    assert_error(
+        get_voting_power(&mut env, &pool, &other.pubkey(), &entries)
+            .await
+            .map(|_| ()),
+        ErrorCode::InvalidVotingEntries,
+    );
+}
+
+#[tokio::test]
+async fn voting_power_boost_decays_with_remaining_lock() {
+    let (mut env, pool) = setup().await;
+    let (user, entries) = staker_with_positions(&mut env, &pool).await;
+
+    env.warp(LOCK_SECONDS / 2).await;
+    assert_eq!(
+        get_voting_power(&mut env, &pool, &user.pubkey(), &entries)
+            .await
+            .unwrap(),
+        STAKE_AMOUNT + STAKE_AMOUNT * 7 / 4
+    );
+    env.warp(LOCK_SECONDS).await;
+    assert_eq!(
+        get_voting_power(&mut env, &pool, &user.pubkey(), &entries)
+            .await
+            .unwrap(),
+        STAKE_AMOUNT * 2
+    );
+}
+
+#[tokio::test]
+async fn snapshot_records_voting_power_at_snapshot_time() {
+    let (mut env, pool) = setup().await;
+    let (user, entries) = staker_with_positions(&mut env, &pool).await;
+    let (other, other_entries) = staker_with_positions(&mut env, &pool).await;
+
+    let snapshot_at = env.now().await;
+    assert_error(
+        snapshot(&mut env, &pool, &user, snapshot_at + 1, &entries).await,
+        ErrorCode::InvalidSnapshotTime,
+    );
+    snapshot(&mut env, &pool, &user, snapshot_at, &entries)
+        .await
+        .unwrap();
+
+    let checkpoint: VotingCheckpoint = env
+        .account(&checkpoint_address(&pool, &user.pubkey(), snapshot_at))
+        .await;
+    assert_eq!(checkpoint.stake_pool, pool.address);
+    assert_eq!(checkpoint.staker, user.pubkey());
+    assert_eq!(checkpoint.snapshot_at, snapshot_at);
+    assert_eq!(checkpoint.voting_power, STAKE_AMOUNT * 7 / 2);
+    assert_eq!(checkpoint.total_voting_power, STAKE_AMOUNT * 7);
+
+    // tokens unstaked and restaked from another wallet after the snapshot point do not vote again
+    env.warp(1).await;
+    env.unstake(&pool, &user, false).await.unwrap();
+    let mover = env.create_user().await;
+    env.fund(&mover.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    let moved_entry = env.init_entry(&pool, &mover).await;
+    env.stake(&pool, &mover, STAKE_AMOUNT, None).await.unwrap();
+    snapshot(&mut env, &pool, &mover, snapshot_at, &[moved_entry])
+        .await
+        .unwrap();
+    let moved: VotingCheckpoint = env
+        .account(&checkpoint_address(&pool, &mover.pubkey(), snapshot_at))
+        .await;
+    assert_eq!(moved.voting_power, 0);
+
+    // boosts are weighted by the lock remaining at the snapshot point, not when it is written
+    env.warp(LOCK_SECONDS).await;
+    snapshot(&mut env, &pool, &other, snapshot_at, &other_entries)
+        .await
+        .unwrap();
+    let later: VotingCheckpoint = env
+        .account(&checkpoint_address(&pool, &other.pubkey(), snapshot_at))
+        .await;
+    assert_eq!(later.voting_power, STAKE_AMOUNT * 7 / 2);
+    let unchanged: VotingCheckpoint = env
+        .account(&checkpoint_address(&pool, &user.pubkey(), snapshot_at))
+        .await;
+    assert_eq!(unchanged.voting_power, STAKE_AMOUNT * 7 / 2);
+}
+
+#[tokio::test]
+async fn stake_added_after_the_snapshot_point_does_not_count() {
+    let (mut env, pool) = setup().await;
+    let admin = env.admin.insecure_clone();
+    env.update_pool(
+        &pool,
+        &admin,
+        stake_pool::instruction::UpdatePoolCompound {
+            compound_enabled: true,
+        },
+    )
+    .await
+    .unwrap();
+    let (user, entries) = staker_with_positions(&mut env, &pool).await;
+    let snapshot_at = env.now().await;
+
+    // a compound keeps the entry's earlier amount, a top-up of the locked position
+    // restarts its lock and the earlier amount votes without the boost
+    env.warp(LOCK_SECONDS / 2).await;
+    env.compound(&pool, &user).await.unwrap();
+    env.fund(&user.pubkey(), &pool.mint, STAKE_AMOUNT).await;
+    env.stake_into(&pool, &user, &entries[1], STAKE_AMOUNT, Some(0))
+        .await
+        .unwrap();
+    snapshot(&mut env, &pool, &user, snapshot_at, &entries)
+        .await
+        .unwrap();
+    let checkpoint: VotingCheckpoint = env
+        .account(&checkpoint_address(&pool, &user.pubkey(), snapshot_at))
+        .await;
+    assert_eq!(checkpoint.voting_power, STAKE_AMOUNT * 2);
+
+    // stake leaving after the snapshot point lowers it
+    env.unstake_partial_from(&pool, &user, &entries[0], STAKE_AMOUNT)
+        .await
+        .unwrap();
+    let voting_power = env
+        .stake_entry(&entries[0])
+        .await
+        .voting_power(snapshot_at)
+        .unwrap();
+    assert!(voting_power < STAKE_AMOUNT);
+}